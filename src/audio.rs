@@ -9,7 +9,8 @@ pub struct InternalAudioPlugin;
 impl Plugin for InternalAudioPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(AudioPlugin)
-            .add_systems(OnEnter(GameState::PlayingDay), start_audio);
+            .add_systems(OnEnter(GameState::PlayingDay), start_audio)
+            .add_systems(OnExit(GameState::PlayingDay), stop_audio);
     }
 }
 
@@ -25,3 +26,9 @@ fn start_audio(mut commands: Commands, audio_assets: Res<AudioAssets>, audio: Re
         .handle();
     commands.insert_resource(FlyingAudio(handle));
 }
+
+/// The flying loop is started afresh every day, so stop the last one
+fn stop_audio(mut commands: Commands, audio: Res<Audio>) {
+    audio.stop();
+    commands.remove_resource::<FlyingAudio>();
+}
@@ -1,21 +1,57 @@
 use crate::input_action::InputAction;
+use crate::GameState;
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
 use bevy::prelude::*;
 use bevy::render::view::RenderLayers;
+use bevy::window::PrimaryWindow;
+use bevy_egui::EguiContexts;
 use leafwing_input_manager::action_state::ActionState;
 
 pub const RENDER_LAYER_MAIN: u8 = 0;
 pub const RENDER_LAYER_OVERLAY: u8 = 1;
 
+/// Zoom limits when there are no [CameraBounds] to derive them from
+const MIN_CAMERA_SCALE: f32 = 0.1;
+const MAX_CAMERA_SCALE: f32 = 3.0;
+const INITIAL_CAMERA_SCALE: f32 = 0.5;
+/// How far in from the overview the camera can zoom
+const MAX_ZOOM_IN: f32 = 8.0;
+/// Extra room around the bounds in the overview, and so how far out the camera can zoom
+const OVERVIEW_MARGIN: f32 = 1.1;
+/// Scale multiplier for each press of zoom, or line scrolled
+const ZOOM_STEP: f32 = 1.2;
+/// Rough size of a line for mice (i.e. touchpads) which scroll in pixels
+const PIXELS_PER_LINE: f32 = 50.0;
+/// Roughly how long the camera takes to catch up with where it should be
+const FOLLOW_SMOOTH_TIME: f32 = 0.25;
+const ZOOM_SMOOTH_TIME: f32 = 0.15;
+
 pub struct CameraPlugin;
 
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, (setup_cameras))
-            .add_systems(FixedUpdate, (camera_track_system, camera_zoom_system));
+        app.add_systems(Startup, setup_cameras)
+            .add_systems(
+                Update,
+                (
+                    camera_mode_system,
+                    camera_zoom_system,
+                    camera_scroll_zoom_system,
+                    camera_pinch_zoom_system,
+                    camera_track_system,
+                )
+                    .chain(),
+            )
+            .add_systems(OnEnter(GameState::PlayingDay), reset_camera_mode_system)
+            .init_resource::<CameraMode>();
     }
 }
+
+/// Each day starts following the lift, rather than wherever the last one was left looking
+fn reset_camera_mode_system(mut camera_mode: ResMut<CameraMode>) {
+    *camera_mode = CameraMode::Follow;
+}
 fn setup_cameras(mut commands: Commands) {
-    let camera_scale = 0.5;
     commands
         .spawn(Camera2dBundle {
             camera: Camera {
@@ -23,7 +59,7 @@ fn setup_cameras(mut commands: Commands) {
                 ..default()
             },
             projection: OrthographicProjection {
-                scale: camera_scale,
+                scale: INITIAL_CAMERA_SCALE,
                 ..default()
             },
             transform: Transform::from_translation(Vec3::new(0.0, 0.0, 1000.0)),
@@ -31,6 +67,10 @@ fn setup_cameras(mut commands: Commands) {
         })
         .insert(RenderLayers::layer(RENDER_LAYER_MAIN))
         .insert(MainCamera)
+        .insert(CameraRig {
+            zoom: INITIAL_CAMERA_SCALE,
+            ..default()
+        })
         .insert(Name::new("MainCamera"));
 
     commands
@@ -54,44 +94,324 @@ pub struct MainCamera;
 #[derive(Component, Debug, Default)]
 pub struct OverlayCamera;
 
+/// Makes the main camera follow this entity. The camera only moves once the entity is more than
+/// [threshold] from the point [offset] from it
 #[derive(Component, Debug, Default)]
 pub struct CameraTrack {
-    pub y_threshold: f32,
+    pub offset: Vec2,
+    pub threshold: Vec2,
 }
 
-fn camera_track_system(
-    mut camera_query: Query<(&mut Transform), (With<Camera>, With<MainCamera>)>,
-    tracked_query: Query<(&Transform, &CameraTrack), Without<Camera>>,
+/// World space area the main camera is kept within, i.e. the building while a day is played
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct CameraBounds(pub Rect);
+
+impl CameraBounds {
+    /// Scale at which all of the bounds fit in [viewport]
+    pub fn overview_scale(&self, viewport: Vec2) -> f32 {
+        let fit = self.0.size() / viewport;
+        fit.x.max(fit.y) * OVERVIEW_MARGIN
+    }
+
+    /// Zoom is limited to between the overview and [MAX_ZOOM_IN] times closer than it
+    pub fn scale_limits(&self, viewport: Vec2) -> (f32, f32) {
+        let max_scale = self.overview_scale(viewport);
+        (max_scale / MAX_ZOOM_IN, max_scale)
+    }
+
+    /// Move [centre] so that an area of size [visible] around it stays inside the bounds, or is
+    /// centred on them along any axis they're too small for
+    pub fn clamp_centre(&self, centre: Vec2, visible: Vec2) -> Vec2 {
+        let half_slack = ((self.0.size() - visible) / 2.0).max(Vec2::ZERO);
+        centre.clamp(self.0.center() - half_slack, self.0.center() + half_slack)
+    }
+}
+
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq)]
+pub enum CameraMode {
+    /// Follow whatever has a [CameraTrack]
+    #[default]
+    Follow,
+    /// Fit all of [CameraBounds] on screen
+    Overview,
+    /// Look at a fixed height, e.g. a floor picked on the minimap
+    Pan { y: f32 },
+}
+
+/// Smoothing state of the main camera
+#[derive(Component, Debug, Default)]
+pub struct CameraRig {
+    velocity: Vec2,
+    scale_velocity: f32,
+    /// The player's chosen scale while following
+    zoom: f32,
+}
+
+/// Critically damped spring towards [target], which gets there as fast as possible without
+/// overshooting, taking roughly [smooth_time]. [velocity] carries over between calls
+pub fn smooth_damp(
+    current: f32,
+    target: f32,
+    velocity: &mut f32,
+    smooth_time: f32,
+    dt: f32,
+) -> f32 {
+    let omega = 2.0 / smooth_time.max(0.0001);
+    let x = omega * dt;
+    // Cheap approximation of e^-x
+    let decay = 1.0 / (1.0 + x + 0.48 * x * x + 0.235 * x * x * x);
+    let change = current - target;
+    let temp = (*velocity + omega * change) * dt;
+    *velocity = (*velocity - omega * temp) * decay;
+    target + (change + temp) * decay
+}
+
+fn scale_limits(bounds: Option<&CameraBounds>, camera: &Camera) -> (f32, f32) {
+    match (bounds, camera.logical_viewport_size()) {
+        (Some(bounds), Some(viewport)) => bounds.scale_limits(viewport),
+        _ => (MIN_CAMERA_SCALE, MAX_CAMERA_SCALE),
+    }
+}
+
+fn camera_mode_system(
+    mut camera_mode: ResMut<CameraMode>,
+    inputs: Query<&ActionState<InputAction>>,
 ) {
-    for (transform, camera_track) in tracked_query.iter() {
-        for (mut camera_transform) in camera_query.iter_mut() {
-            let y = f32::clamp(
-                camera_transform.translation.y,
-                transform.translation.y - camera_track.y_threshold,
-                transform.translation.y + camera_track.y_threshold,
-            );
-            camera_transform.translation.y = y;
-            camera_transform.translation.x = transform.translation.x - 200.0;
+    if let Ok(inputs) = inputs.get_single() {
+        if inputs.just_pressed(&InputAction::ToggleOverview) {
+            *camera_mode = match *camera_mode {
+                CameraMode::Follow => CameraMode::Overview,
+                CameraMode::Overview | CameraMode::Pan { .. } => CameraMode::Follow,
+            };
         }
     }
 }
 
 fn camera_zoom_system(
-    mut camera_query: Query<(&mut OrthographicProjection), (With<Camera>, With<MainCamera>)>,
+    mut camera_query: Query<(&Camera, &mut CameraRig), With<MainCamera>>,
     inputs: Query<&ActionState<InputAction>>,
+    bounds: Option<Res<CameraBounds>>,
+    mut camera_mode: ResMut<CameraMode>,
 ) {
-    if let Ok(inputs) = inputs.get_single() {
-        let mut scale_diff = 0.0;
-        if inputs.just_pressed(&InputAction::ZoomIn) {
-            println!("Zoom In");
-            scale_diff += 0.1;
+    let Ok(inputs) = inputs.get_single() else {
+        return;
+    };
+    let mut scale_factor = 1.0;
+    if inputs.just_pressed(&InputAction::ZoomIn) {
+        scale_factor /= ZOOM_STEP;
+    }
+    if inputs.just_pressed(&InputAction::ZoomOut) {
+        scale_factor *= ZOOM_STEP;
+    }
+    if scale_factor == 1.0 {
+        return;
+    }
+    if *camera_mode == CameraMode::Overview {
+        *camera_mode = CameraMode::Follow;
+    }
+    for (camera, mut rig) in camera_query.iter_mut() {
+        let (min_scale, max_scale) = scale_limits(bounds.as_deref(), camera);
+        rig.zoom = f32::clamp(rig.zoom * scale_factor, min_scale, max_scale);
+    }
+}
+
+/// Zooms around the cursor, so whatever's under it stays put
+fn camera_scroll_zoom_system(
+    mut camera_query: Query<
+        (
+            &Camera,
+            &GlobalTransform,
+            &mut Transform,
+            &mut OrthographicProjection,
+            &mut CameraRig,
+        ),
+        With<MainCamera>,
+    >,
+    mut wheel_events: EventReader<MouseWheel>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut contexts: EguiContexts,
+    bounds: Option<Res<CameraBounds>>,
+    mut camera_mode: ResMut<CameraMode>,
+) {
+    let lines: f32 = wheel_events
+        .read()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / PIXELS_PER_LINE,
+        })
+        .sum();
+    // Scrolling over ui windows is for them
+    if lines == 0.0 || contexts.ctx_mut().is_pointer_over_area() {
+        return;
+    }
+    let Some(cursor) = window_query
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position())
+    else {
+        return;
+    };
+    if *camera_mode == CameraMode::Overview {
+        *camera_mode = CameraMode::Follow;
+    }
+    for (camera, global_transform, mut transform, mut projection, mut rig) in
+        camera_query.iter_mut()
+    {
+        let (min_scale, max_scale) = scale_limits(bounds.as_deref(), camera);
+        let scale = f32::clamp(
+            projection.scale * ZOOM_STEP.powf(-lines),
+            min_scale,
+            max_scale,
+        );
+        if let Some(cursor_world) = camera.viewport_to_world_2d(global_transform, cursor) {
+            let centre = transform.translation.truncate();
+            let new_centre = cursor_world - (cursor_world - centre) * scale / projection.scale;
+            transform.translation = new_centre.extend(transform.translation.z);
         }
-        if inputs.just_pressed(&InputAction::ZoomOut) {
-            println!("Zoom Out");
-            scale_diff -= 0.1;
+        projection.scale = scale;
+        rig.zoom = scale;
+        rig.scale_velocity = 0.0;
+    }
+}
+
+/// Touch alternative to [camera_zoom_system], spreading two fingers apart zooms in
+fn camera_pinch_zoom_system(
+    mut camera_query: Query<(&Camera, &mut CameraRig), With<MainCamera>>,
+    touches: Res<Touches>,
+    bounds: Option<Res<CameraBounds>>,
+) {
+    let mut pressed = touches.iter();
+    let (Some(first), Some(second), None) = (pressed.next(), pressed.next(), pressed.next()) else {
+        return;
+    };
+    let previous_distance = first
+        .previous_position()
+        .distance(second.previous_position());
+    let distance = first.position().distance(second.position());
+    if previous_distance <= 0.0 || distance <= 0.0 {
+        return;
+    }
+    for (camera, mut rig) in camera_query.iter_mut() {
+        let (min_scale, max_scale) = scale_limits(bounds.as_deref(), camera);
+        rig.zoom = f32::clamp(
+            rig.zoom * previous_distance / distance,
+            min_scale,
+            max_scale,
+        );
+    }
+}
+
+/// Real time, so the camera can still be moved about while the game's paused
+fn camera_track_system(
+    mut camera_query: Query<
+        (
+            &Camera,
+            &mut Transform,
+            &mut OrthographicProjection,
+            &mut CameraRig,
+        ),
+        With<MainCamera>,
+    >,
+    tracked_query: Query<(&Transform, &CameraTrack), Without<Camera>>,
+    camera_mode: Res<CameraMode>,
+    bounds: Option<Res<CameraBounds>>,
+    time: Res<Time<Real>>,
+) {
+    let dt = time.delta_seconds();
+    for (camera, mut camera_transform, mut projection, mut rig) in camera_query.iter_mut() {
+        let viewport = camera.logical_viewport_size();
+        let centre = camera_transform.translation.truncate();
+        let (target_centre, target_scale) = match (*camera_mode, bounds.as_deref(), viewport) {
+            (CameraMode::Overview, Some(bounds), Some(viewport)) => {
+                (bounds.0.center(), bounds.overview_scale(viewport))
+            }
+            (CameraMode::Pan { y }, _, _) => (Vec2::new(centre.x, y), rig.zoom),
+            _ => {
+                let target_centre =
+                    tracked_query
+                        .iter()
+                        .next()
+                        .map_or(centre, |(transform, camera_track)| {
+                            let tracked = transform.translation.truncate() + camera_track.offset;
+                            centre.clamp(
+                                tracked - camera_track.threshold,
+                                tracked + camera_track.threshold,
+                            )
+                        });
+                (target_centre, rig.zoom)
+            }
+        };
+
+        let mut scale_velocity = rig.scale_velocity;
+        projection.scale = smooth_damp(
+            projection.scale,
+            target_scale,
+            &mut scale_velocity,
+            ZOOM_SMOOTH_TIME,
+            dt,
+        );
+        rig.scale_velocity = scale_velocity;
+
+        let mut velocity = rig.velocity;
+        let mut new_centre = Vec2::new(
+            smooth_damp(
+                centre.x,
+                target_centre.x,
+                &mut velocity.x,
+                FOLLOW_SMOOTH_TIME,
+                dt,
+            ),
+            smooth_damp(
+                centre.y,
+                target_centre.y,
+                &mut velocity.y,
+                FOLLOW_SMOOTH_TIME,
+                dt,
+            ),
+        );
+        if let (Some(bounds), Some(viewport)) = (bounds.as_deref(), viewport) {
+            let clamped = bounds.clamp_centre(new_centre, viewport * projection.scale);
+            // Don't keep pushing against the bounds
+            if clamped.x != new_centre.x {
+                velocity.x = 0.0;
+            }
+            if clamped.y != new_centre.y {
+                velocity.y = 0.0;
+            }
+            new_centre = clamped;
         }
-        for (mut projection) in camera_query.iter_mut() {
-            projection.scale += scale_diff;
+        rig.velocity = velocity;
+        camera_transform.translation = new_centre.extend(camera_transform.translation.z);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_smooth_damp_settles_without_overshoot() {
+        let mut position = 0.0;
+        let mut velocity = 0.0;
+        for _ in 0..120 {
+            position = smooth_damp(position, 100.0, &mut velocity, 0.25, 1.0 / 60.0);
+            assert!(position <= 100.0);
         }
+        assert!((position - 100.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_clamp_centre() {
+        let bounds = CameraBounds(Rect::new(-100.0, -500.0, 100.0, 500.0));
+        let visible = Vec2::new(400.0, 200.0);
+        // Too narrow, so centred horizontally, but kept inside vertically
+        assert_eq!(
+            bounds.clamp_centre(Vec2::new(50.0, 450.0), visible),
+            Vec2::new(0.0, 400.0)
+        );
+        assert_eq!(
+            bounds.clamp_centre(Vec2::new(0.0, -20.0), visible),
+            Vec2::new(0.0, -20.0)
+        );
     }
 }
@@ -1,8 +1,17 @@
+use crate::settings::Settings;
 use bevy::math::Vec3;
 use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::EguiSettings;
 use derive_new::new;
 use std::time::Duration;
 
+/// The window size the ui is designed for, [LayoutScale] is 1 at this size
+const REFERENCE_WINDOW_SIZE: Vec2 = Vec2::new(1280.0, 720.0);
+/// Don't shrink past the point things are too small to tap, or grow absurdly large
+const MIN_LAYOUT_SCALE: f32 = 0.6;
+const MAX_LAYOUT_SCALE: f32 = 3.0;
+
 pub struct CorePlugin;
 
 impl Plugin for CorePlugin {
@@ -10,17 +19,83 @@ impl Plugin for CorePlugin {
         app.add_systems(
             Update,
             (
-                With2DScale::apply_system,
-                InScreenSpaceLocation::apply_position_system,
-                TransformTween::update_system,
-            ),
+                LayoutScale::update_system,
+                (
+                    With2DScale::apply_system,
+                    InScreenSpaceLocation::apply_position_system,
+                ),
+            )
+                .chain(),
         )
+        // Tweens gate when humans become available to be picked up, so they run with the rest
+        // of the day's simulation
+        .add_systems(FixedUpdate, TransformTween::update_system)
         .add_event::<TweenCompleteEvent>()
+        .init_resource::<LayoutScale>()
         .register_type::<InScreenSpaceLocation>()
         .register_type::<TransformTween>()
         .register_type::<With2DScale>();
     }
 }
+/// How much bigger than designed the screen space ui should be drawn, so it stays in proportion
+/// to the window. Driven by the window's logical size, which already accounts for its scale
+/// factor, times the player's [Settings::ui_scale]
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Reflect)]
+pub struct LayoutScale(pub f32);
+
+impl Default for LayoutScale {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+impl LayoutScale {
+    pub fn of_window(logical_size: Vec2, user_scale: f32) -> Self {
+        Self(Self::fit(logical_size).clamp(MIN_LAYOUT_SCALE, MAX_LAYOUT_SCALE) * user_scale)
+    }
+
+    /// The factor for egui and Bevy UI. Their sizes are already in logical pixels, which are
+    /// about the right size to tap on a phone, so unlike the sprites they only ever grow with
+    /// the window
+    pub fn ui_factor_of_window(logical_size: Vec2, user_scale: f32) -> f32 {
+        Self::fit(logical_size).clamp(1.0, MAX_LAYOUT_SCALE) * user_scale
+    }
+
+    fn fit(logical_size: Vec2) -> f32 {
+        let fit = logical_size / REFERENCE_WINDOW_SIZE;
+        fit.x.min(fit.y)
+    }
+
+    /// Also scales egui and Bevy UI, see [LayoutScale::ui_factor_of_window]
+    fn update_system(
+        windows: Query<Ref<Window>, With<PrimaryWindow>>,
+        settings: Res<Settings>,
+        mut layout_scale: ResMut<Self>,
+        mut egui_settings: ResMut<EguiSettings>,
+        mut ui_scale: ResMut<UiScale>,
+    ) {
+        let Ok(window) = windows.get_single() else {
+            return;
+        };
+        if !window.is_changed() && !settings.is_changed() {
+            return;
+        }
+        let logical_size = Vec2::new(window.width(), window.height());
+        let new_scale = Self::of_window(logical_size, settings.ui_scale);
+        let ui_factor = Self::ui_factor_of_window(logical_size, settings.ui_scale);
+        // Window changes every time the cursor moves, only touch things when it matters
+        if *layout_scale != new_scale {
+            *layout_scale = new_scale;
+        }
+        if ui_scale.0 != ui_factor {
+            egui_settings.scale_factor = ui_factor as f64;
+            ui_scale.0 = ui_factor;
+        }
+    }
+}
+
+/// Scales screen space things by [scale] times the [LayoutScale]. Only root entities get the
+/// [LayoutScale], children are already scaled with their parent
 #[derive(Clone, Debug, Reflect, Component)]
 pub struct With2DScale {
     pub scale: f32,
@@ -31,9 +106,19 @@ impl With2DScale {
         Self { scale }
     }
 
-    fn apply_system(mut query: Query<(&mut Transform, &Self), Changed<Self>>) {
-        for (mut transform, with_scale) in query.iter_mut() {
-            transform.scale = Vec3::new(with_scale.scale, with_scale.scale, transform.scale.z);
+    fn apply_system(
+        mut query: Query<(&mut Transform, Ref<Self>, Option<&Parent>)>,
+        layout_scale: Res<LayoutScale>,
+    ) {
+        for (mut transform, with_scale, parent) in query.iter_mut() {
+            if !with_scale.is_changed() && !layout_scale.is_changed() {
+                continue;
+            }
+            let scale = match parent {
+                Some(_) => with_scale.scale,
+                None => with_scale.scale * layout_scale.0,
+            };
+            transform.scale = Vec3::new(scale, scale, transform.scale.z);
         }
     }
 }
@@ -79,18 +164,30 @@ impl InScreenSpaceLocation {
     pub fn new(anchor: ScreenSpaceAnchor, offset: f32) -> Self {
         Self { anchor, offset }
     }
+
+    /// Position in the overlay's space, which is logical pixels with the origin in the middle
+    /// of the window. [offset] grows with [layout_scale] along with everything else
+    pub fn position(&self, window_size: Vec2, layout_scale: f32) -> Vec2 {
+        let raw_position = self.anchor.to_signum() * window_size / 2.0;
+        let offset = self.anchor.to_inverted_signum() * self.offset * layout_scale;
+        raw_position + offset
+    }
+
+    /// Locations are only re-applied when they, the window or the [LayoutScale] change
     fn apply_position_system(
-        mut query: Query<(&mut Transform, &Self)>,
-        windows: Query<&Window, Or<(Changed<Window>, Changed<Self>)>>,
+        mut query: Query<(&mut Transform, Ref<Self>)>,
+        windows: Query<Ref<Window>, With<PrimaryWindow>>,
+        layout_scale: Res<LayoutScale>,
     ) {
-        // TODO: Verify the Changed<Window> actually works
-        if let Ok(window) = windows.get_single() {
-            for (mut transform, screen_space_location) in query.iter_mut() {
-                let raw_position = screen_space_location.anchor.to_signum()
-                    * Vec2::new(window.width() / 2.0, window.height() / 2.0);
-                let offset = screen_space_location.anchor.to_inverted_signum()
-                    * Vec2::new(screen_space_location.offset, screen_space_location.offset);
-                transform.translation = (raw_position + offset).extend(transform.translation.z);
+        let Ok(window) = windows.get_single() else {
+            return;
+        };
+        let refresh_all = window.is_changed() || layout_scale.is_changed();
+        let window_size = Vec2::new(window.width(), window.height());
+        for (mut transform, screen_space_location) in query.iter_mut() {
+            if refresh_all || screen_space_location.is_changed() {
+                let position = screen_space_location.position(window_size, layout_scale.0);
+                transform.translation = position.extend(transform.translation.z);
             }
         }
     }
@@ -118,7 +215,7 @@ impl TransformTween {
             elapsed: 0.0,
         }
     }
-    fn update_system(
+    pub fn update_system(
         time: Res<Time>,
         mut query: Query<(Entity, &mut Transform, &mut Self)>,
         mut commands: Commands,
@@ -174,3 +271,84 @@ impl<T: Lerpable + Component + Copy> LerpTween<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(anchor: ScreenSpaceAnchor, layout_scale: f32) -> Vec2 {
+        InScreenSpaceLocation::new(anchor, 20.0).position(Vec2::new(800.0, 600.0), layout_scale)
+    }
+
+    #[test]
+    fn test_anchor_positions() {
+        assert_eq!(position(ScreenSpaceAnchor::Top, 1.0), Vec2::new(0.0, 280.0));
+        assert_eq!(
+            position(ScreenSpaceAnchor::Bottom, 1.0),
+            Vec2::new(0.0, -280.0)
+        );
+        assert_eq!(
+            position(ScreenSpaceAnchor::Left, 1.0),
+            Vec2::new(-380.0, 0.0)
+        );
+        assert_eq!(
+            position(ScreenSpaceAnchor::Right, 1.0),
+            Vec2::new(380.0, 0.0)
+        );
+        assert_eq!(
+            position(ScreenSpaceAnchor::TopLeft, 1.0),
+            Vec2::new(-380.0, 280.0)
+        );
+        assert_eq!(
+            position(ScreenSpaceAnchor::TopRight, 1.0),
+            Vec2::new(380.0, 280.0)
+        );
+        assert_eq!(
+            position(ScreenSpaceAnchor::BottomLeft, 1.0),
+            Vec2::new(-380.0, -280.0)
+        );
+        assert_eq!(
+            position(ScreenSpaceAnchor::BottomRight, 1.0),
+            Vec2::new(380.0, -280.0)
+        );
+    }
+
+    #[test]
+    fn test_anchor_offset_scales() {
+        assert_eq!(
+            position(ScreenSpaceAnchor::Right, 2.0),
+            Vec2::new(360.0, 0.0)
+        );
+        assert_eq!(
+            position(ScreenSpaceAnchor::BottomLeft, 0.5),
+            Vec2::new(-390.0, -290.0)
+        );
+    }
+
+    #[test]
+    fn test_layout_scale() {
+        assert_eq!(LayoutScale::of_window(REFERENCE_WINDOW_SIZE, 1.0).0, 1.0);
+        // 4K, limited by its height
+        assert_eq!(
+            LayoutScale::of_window(Vec2::new(3840.0, 2160.0), 1.0).0,
+            3.0
+        );
+        // Portrait phone, limited by its width but not below the minimum
+        assert_eq!(
+            LayoutScale::of_window(Vec2::new(390.0, 844.0), 1.0).0,
+            MIN_LAYOUT_SCALE
+        );
+        assert_eq!(LayoutScale::of_window(REFERENCE_WINDOW_SIZE, 1.5).0, 1.5);
+    }
+
+    #[test]
+    fn test_ui_factor_doesnt_shrink_on_phones() {
+        let phone = Vec2::new(390.0, 844.0);
+        assert!(LayoutScale::ui_factor_of_window(phone, 1.0) >= 1.0);
+        assert_eq!(
+            LayoutScale::ui_factor_of_window(Vec2::new(3840.0, 2160.0), 1.0),
+            3.0
+        );
+        assert_eq!(LayoutScale::ui_factor_of_window(phone, 1.5), 1.5);
+    }
+}
@@ -0,0 +1,458 @@
+//! The campaign: a run of days, each in its own building with its own objectives. How well a
+//! day's objectives are met earns it up to [MAX_STARS] stars, and each day is unlocked by earning
+//! at least one star on the day before. Best results are kept in [PROGRESS_PATH].
+
+use crate::game::endless::EndlessRun;
+use crate::game::game::DayScopedResourceExt;
+use crate::game::game_clock::GameTimeConfig;
+use crate::game::seed::DaySeed;
+use crate::game::telemetry::{DayEndedEvent, SessionLog};
+use crate::game::ui::{self, GameCentralInfo};
+use crate::game::world_gen::{Class, Level, LevelKind};
+use crate::storage;
+use crate::GameState;
+use bevy::prelude::*;
+use bevy_egui::egui::{Align2, Color32, RichText};
+use bevy_egui::{egui, EguiContexts};
+use std::ops::Range;
+
+const PROGRESS_PATH: &str = "campaign.cfg";
+pub const MAX_STARS: u8 = 3;
+
+pub struct CampaignPlugin;
+
+impl Plugin for CampaignPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                day_results_system,
+                objectives_window_system,
+                results_window_system,
+            )
+                .chain()
+                .run_if(in_state(GameState::PlayingDay))
+                .run_if(resource_exists::<ActiveCampaignDay>),
+        )
+        // Inserted by [day_results_system]
+        .remove_on_day_exit::<DayResults>()
+        .insert_resource(CampaignProgress::load_or_default());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ObjectiveKind {
+    /// Deliver at least this many humans
+    Deliver,
+    /// Keep humans waiting on average at most this many seconds to be picked up
+    AverageWaitUnder,
+    /// Let at most this many humans run out of patience, waiting on a floor or in the lift
+    MaxAbandonments,
+}
+
+/// An objective with the thresholds for one, two and three stars
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Objective {
+    pub kind: ObjectiveKind,
+    pub thresholds: [f32; MAX_STARS as usize],
+}
+
+impl Objective {
+    const fn new(kind: ObjectiveKind, thresholds: [f32; MAX_STARS as usize]) -> Self {
+        Self { kind, thresholds }
+    }
+
+    /// None if there's nothing to measure, which earns no stars
+    fn value(&self, results: &DayResults) -> Option<f32> {
+        match self.kind {
+            ObjectiveKind::Deliver => Some(results.deliveries as f32),
+            ObjectiveKind::AverageWaitUnder => results.average_wait_secs,
+            ObjectiveKind::MaxAbandonments => Some(results.abandonments as f32),
+        }
+    }
+
+    pub fn stars(&self, results: &DayResults) -> u8 {
+        let Some(value) = self.value(results) else {
+            return 0;
+        };
+        self.thresholds
+            .iter()
+            .filter(|threshold| match self.kind {
+                ObjectiveKind::Deliver => value >= **threshold,
+                ObjectiveKind::AverageWaitUnder | ObjectiveKind::MaxAbandonments => {
+                    value <= **threshold
+                }
+            })
+            .count() as u8
+    }
+
+    pub fn describe(&self) -> String {
+        let [one, two, three] = self.thresholds;
+        match self.kind {
+            ObjectiveKind::Deliver => format!("Deliver {} / {} / {} people", one, two, three),
+            ObjectiveKind::AverageWaitUnder => {
+                format!("Average wait under {}s / {}s / {}s", one, two, three)
+            }
+            ObjectiveKind::MaxAbandonments => {
+                format!("At most {} / {} / {} abandonments", one, two, three)
+            }
+        }
+    }
+
+    pub fn describe_result(&self, results: &DayResults) -> String {
+        match self.kind {
+            ObjectiveKind::Deliver => format!("{} delivered", results.deliveries),
+            ObjectiveKind::AverageWaitUnder => match results.average_wait_secs {
+                Some(average_wait_secs) => format!("{:.1}s average wait", average_wait_secs),
+                None => "Nobody waited".to_string(),
+            },
+            ObjectiveKind::MaxAbandonments => format!("{} abandoned", results.abandonments),
+        }
+    }
+}
+
+/// What the objectives are measured against, taken from the [SessionLog]
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct DayResults {
+    pub deliveries: usize,
+    /// None if nobody turned up
+    pub average_wait_secs: Option<f32>,
+    pub abandonments: usize,
+}
+
+impl DayResults {
+    pub fn of_log(session_log: &SessionLog, now_secs: f32) -> Self {
+        Self {
+            deliveries: session_log.deliveries(),
+            average_wait_secs: session_log.average_wait_secs(now_secs),
+            abandonments: session_log.abandonments(),
+        }
+    }
+}
+
+pub struct CampaignDay {
+    pub name: &'static str,
+    /// Campaign days are the same every time they're played
+    pub seed: u64,
+    pub class: Class,
+    pub floor_count: usize,
+    pub working_hours: Range<u8>,
+    pub objectives: &'static [Objective],
+}
+
+impl CampaignDay {
+    pub fn level(&self) -> Level {
+        Level::with_floor_count(LevelKind::Hotel, self.class, self.floor_count)
+    }
+
+    pub fn time_config(&self) -> GameTimeConfig {
+        GameTimeConfig::new(
+            GameTimeConfig::default().time_per_day(),
+            self.working_hours.clone(),
+        )
+    }
+
+    /// The fewest stars earned by any one objective
+    pub fn stars(&self, results: &DayResults) -> u8 {
+        self.objectives
+            .iter()
+            .map(|objective| objective.stars(results))
+            .min()
+            .unwrap_or(MAX_STARS)
+    }
+}
+
+pub const CAMPAIGN_DAYS: &[CampaignDay] = &[
+    CampaignDay {
+        name: "First Shift",
+        seed: 1,
+        class: Class::One,
+        floor_count: 5,
+        working_hours: 8..18,
+        objectives: &[Objective::new(ObjectiveKind::Deliver, [10.0, 20.0, 30.0])],
+    },
+    CampaignDay {
+        name: "Check In",
+        seed: 2,
+        class: Class::One,
+        floor_count: 8,
+        working_hours: 8..20,
+        objectives: &[
+            Objective::new(ObjectiveKind::Deliver, [20.0, 35.0, 50.0]),
+            Objective::new(ObjectiveKind::MaxAbandonments, [5.0, 2.0, 0.0]),
+        ],
+    },
+    CampaignDay {
+        name: "Rush Hour",
+        seed: 3,
+        class: Class::Two,
+        floor_count: 10,
+        working_hours: 6..22,
+        objectives: &[
+            Objective::new(ObjectiveKind::Deliver, [30.0, 45.0, 60.0]),
+            Objective::new(ObjectiveKind::AverageWaitUnder, [40.0, 30.0, 20.0]),
+        ],
+    },
+    CampaignDay {
+        name: "Conference",
+        seed: 4,
+        class: Class::Two,
+        floor_count: 14,
+        working_hours: 6..22,
+        objectives: &[
+            Objective::new(ObjectiveKind::Deliver, [35.0, 50.0, 65.0]),
+            Objective::new(ObjectiveKind::AverageWaitUnder, [40.0, 30.0, 20.0]),
+            Objective::new(ObjectiveKind::MaxAbandonments, [5.0, 2.0, 0.0]),
+        ],
+    },
+    CampaignDay {
+        name: "Full House",
+        seed: 5,
+        class: Class::Three,
+        floor_count: 20,
+        working_hours: 0..24,
+        objectives: &[
+            Objective::new(ObjectiveKind::Deliver, [40.0, 60.0, 80.0]),
+            Objective::new(ObjectiveKind::AverageWaitUnder, [35.0, 25.0, 15.0]),
+            Objective::new(ObjectiveKind::MaxAbandonments, [3.0, 1.0, 0.0]),
+        ],
+    },
+];
+
+/// Set up the next [GameState::PlayingDay] to be the campaign day at [index]
+pub fn start_campaign_day(
+    index: usize,
+    commands: &mut Commands,
+    day_seed: &mut DaySeed,
+    time_config: &mut GameTimeConfig,
+    level: &mut Level,
+) {
+    let day = &CAMPAIGN_DAYS[index];
+    info!("Starting campaign day {} ({})", index + 1, day.name);
+    day_seed.0 = day.seed;
+    *time_config = day.time_config();
+    *level = day.level();
+    commands.insert_resource(ActiveCampaignDay(index));
+    commands.remove_resource::<EndlessRun>();
+}
+
+/// Present while the day being played is from the campaign, the index into [CAMPAIGN_DAYS]
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct ActiveCampaignDay(pub usize);
+
+/// The best stars earned on each campaign day
+#[derive(Resource, Debug, Default, Clone, PartialEq)]
+pub struct CampaignProgress {
+    stars: Vec<u8>,
+}
+
+impl CampaignProgress {
+    pub fn stars(&self, index: usize) -> u8 {
+        self.stars.get(index).copied().unwrap_or(0)
+    }
+
+    pub fn is_unlocked(&self, index: usize) -> bool {
+        index == 0 || self.stars(index - 1) > 0
+    }
+
+    /// Returns true if [stars] beats the best so far for the day
+    pub fn record(&mut self, index: usize, stars: u8) -> bool {
+        if self.stars.len() <= index {
+            self.stars.resize(index + 1, 0);
+        }
+        let improved = stars > self.stars[index];
+        if improved {
+            self.stars[index] = stars;
+        }
+        improved
+    }
+
+    fn to_cfg(&self) -> String {
+        self.stars
+            .iter()
+            .enumerate()
+            .map(|(index, stars)| format!("{}={}\n", index, stars))
+            .collect()
+    }
+
+    /// Unknown lines are skipped, as with the keybindings
+    fn of_cfg(cfg: &str) -> Self {
+        let mut progress = Self::default();
+        for line in cfg.lines() {
+            let parsed = line.split_once('=').and_then(|(index, stars)| {
+                Some((index.trim().parse().ok()?, stars.trim().parse().ok()?))
+            });
+            match parsed {
+                Some((index, stars)) if index < CAMPAIGN_DAYS.len() => {
+                    progress.record(index, u8::min(stars, MAX_STARS));
+                }
+                _ => warn!("Ignoring unknown campaign progress: {}", line),
+            }
+        }
+        progress
+    }
+
+    pub fn load_or_default() -> Self {
+        match storage::read_to_string(PROGRESS_PATH) {
+            Ok(cfg) => Self::of_cfg(&cfg),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) {
+        if let Err(error) = storage::write(PROGRESS_PATH, &self.to_cfg()) {
+            warn!("Failed to save campaign progress: {:?}", error);
+        }
+    }
+}
+
+/// Filled and empty stars out of [MAX_STARS]
+pub fn stars_text(stars: u8) -> String {
+    (0..MAX_STARS)
+        .map(|i| if i < stars { '★' } else { '☆' })
+        .collect()
+}
+
+fn day_results_system(
+    mut commands: Commands,
+    mut day_ended_events: EventReader<DayEndedEvent>,
+    active_day: Res<ActiveCampaignDay>,
+    session_log: Res<SessionLog>,
+    game_central_info: Res<GameCentralInfo>,
+    mut progress: ResMut<CampaignProgress>,
+) {
+    if day_ended_events.read().count() == 0 {
+        return;
+    }
+    let day = &CAMPAIGN_DAYS[active_day.0];
+    let results = DayResults::of_log(&session_log, game_central_info.time.elapsed_secs());
+    let stars = day.stars(&results);
+    info!("Finished {} with {} stars: {:?}", day.name, stars, results);
+    if progress.record(active_day.0, stars) {
+        progress.save();
+    }
+    commands.insert_resource(results);
+}
+
+fn objectives_window_system(
+    mut contexts: EguiContexts,
+    active_day: Res<ActiveCampaignDay>,
+    session_log: Res<SessionLog>,
+    game_central_info: Res<GameCentralInfo>,
+    day_results: Option<Res<DayResults>>,
+) {
+    if day_results.is_some() {
+        return;
+    }
+    let day = &CAMPAIGN_DAYS[active_day.0];
+    let results = DayResults::of_log(&session_log, game_central_info.time.elapsed_secs());
+    egui::Window::new("Objectives")
+        .movable(false)
+        .anchor(Align2::LEFT_BOTTOM, egui::Vec2::ZERO)
+        .title_bar(false)
+        .frame(ui::default_frame())
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label(RichText::new(day.name).color(Color32::WHITE).strong());
+            for objective in day.objectives.iter() {
+                ui.label(
+                    RichText::new(format!(
+                        "{} {}: {}",
+                        stars_text(objective.stars(&results)),
+                        objective.describe(),
+                        objective.describe_result(&results)
+                    ))
+                    .color(Color32::WHITE),
+                );
+            }
+        });
+}
+
+fn results_window_system(
+    mut contexts: EguiContexts,
+    active_day: Res<ActiveCampaignDay>,
+    day_results: Option<Res<DayResults>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(results) = day_results else {
+        return;
+    };
+    let day = &CAMPAIGN_DAYS[active_day.0];
+    egui::Window::new(format!("{} Complete", day.name))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.vertical_centered(|ui| {
+                ui.label(
+                    RichText::new(stars_text(day.stars(&results)))
+                        .size(40.0)
+                        .color(Color32::GOLD),
+                );
+                for objective in day.objectives.iter() {
+                    ui.label(format!(
+                        "{} {}",
+                        stars_text(objective.stars(&results)),
+                        objective.describe_result(&results)
+                    ));
+                }
+                if ui.button("Retry").clicked() {
+                    next_state.set(GameState::RestartingDay);
+                }
+                if ui.button("Back to Menu").clicked() {
+                    next_state.set(GameState::PlayingMenu);
+                }
+            });
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results(deliveries: usize, average_wait_secs: f32, abandonments: usize) -> DayResults {
+        DayResults {
+            deliveries,
+            average_wait_secs: Some(average_wait_secs),
+            abandonments,
+        }
+    }
+
+    #[test]
+    fn test_stars() {
+        let day = &CAMPAIGN_DAYS[3];
+        assert_eq!(day.stars(&results(0, 0.0, 0)), 0);
+        assert_eq!(day.stars(&results(50, 25.0, 0)), 2);
+        assert_eq!(day.stars(&results(100, 10.0, 0)), 3);
+        // The worst objective decides
+        assert_eq!(day.stars(&results(100, 10.0, 4)), 1);
+    }
+
+    #[test]
+    fn test_no_wait_earns_no_stars() {
+        let objective = Objective::new(ObjectiveKind::AverageWaitUnder, [30.0, 20.0, 10.0]);
+        let nobody = DayResults {
+            average_wait_secs: None,
+            ..results(0, 0.0, 0)
+        };
+        assert_eq!(objective.stars(&nobody), 0);
+        assert_eq!(objective.stars(&results(0, 0.0, 0)), 3);
+    }
+
+    #[test]
+    fn test_progress() {
+        let mut progress = CampaignProgress::default();
+        assert!(progress.is_unlocked(0));
+        assert!(!progress.is_unlocked(1));
+        assert!(progress.record(0, 2));
+        assert!(!progress.record(0, 1));
+        assert_eq!(progress.stars(0), 2);
+        assert!(progress.is_unlocked(1));
+        assert!(!progress.is_unlocked(2));
+        assert_eq!(CampaignProgress::of_cfg(&progress.to_cfg()), progress);
+        assert_eq!(CampaignProgress::of_cfg("0=9\nnope\n").stars(0), MAX_STARS);
+        assert_eq!(
+            CampaignProgress::of_cfg("4000000000=1\n"),
+            CampaignProgress::default()
+        );
+    }
+}
@@ -0,0 +1,472 @@
+//! A developer console, showing the game's logs and taking cheat commands. Logs reach it through
+//! a tracing layer, see [add_console_layer]
+
+use crate::game::debug::DebugGizmos;
+use crate::game::floors::{floor_num_pretty_str, Floors, LiftLimits, SpawnHumansEvent};
+use crate::game::game::{Lift, LinearVelocity};
+use crate::game::game_clock::TimeOfDay;
+use crate::game::spawn_simulation::FloorSpawnManager;
+use crate::game::ui::GameCentralInfo;
+use crate::game::FloorNum;
+use crate::input_action::InputAction;
+use crate::GameState;
+use bevy::log::tracing_subscriber::layer::{Context, SubscriberExt};
+use bevy::log::tracing_subscriber::Layer;
+use bevy::log::BoxedSubscriber;
+use bevy::prelude::*;
+use bevy::utils::tracing::field::{Field, Visit};
+use bevy::utils::tracing::{Level, Subscriber};
+use bevy_egui::{egui, EguiContexts};
+use leafwing_input_manager::plugin::InputManagerSystem;
+use leafwing_input_manager::prelude::*;
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::Mutex;
+
+/// Older lines are dropped past this
+const MAX_LOG_LINES: usize = 1000;
+const LEVELS: [Level; 5] = [
+    Level::ERROR,
+    Level::WARN,
+    Level::INFO,
+    Level::DEBUG,
+    Level::TRACE,
+];
+const HELP: &str = "Commands:
+  spawn <count> <floor> <destination>  spawn humans waiting at a floor
+  time <HH:MM>                         set the clock
+  money <amount>                       add (or with a negative amount take) money
+  tp <floor>                           teleport the lift to a floor
+  gizmo <lift|dial|proximity> [on|off] toggle a debug gizmo
+  spawns                               dump the floor spawn manager's state
+  clear                                clear the log
+  help                                 show this";
+
+/// Lines logged by every thread, filled by [ConsoleLayer]. A static as the layer is built
+/// before the app's world exists
+static LOG_LINES: Mutex<VecDeque<LogLine>> = Mutex::new(VecDeque::new());
+
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PreUpdate,
+            consume_typed_actions_system
+                .after(InputManagerSystem::Update)
+                .run_if(in_state(GameState::PlayingDay)),
+        )
+        .add_systems(
+            Update,
+            (
+                console_toggle_system,
+                console_window_system,
+                run_command_system,
+            )
+                .chain()
+                .run_if(in_state(GameState::PlayingDay)),
+        )
+        .init_resource::<ConsoleState>()
+        .add_event::<ConsoleCommand>();
+    }
+}
+
+/// For [bevy::log::LogPlugin::update_subscriber], so logs also show in the console
+pub fn add_console_layer(subscriber: BoxedSubscriber) -> BoxedSubscriber {
+    Box::new(subscriber.with(ConsoleLayer))
+}
+
+#[derive(Debug, Clone)]
+struct LogLine {
+    level: Level,
+    target: String,
+    message: String,
+}
+
+impl LogLine {
+    fn matches(&self, max_level: Level, filter: &str) -> bool {
+        // More verbose levels compare greater
+        self.level <= max_level
+            && (filter.is_empty() || self.target.contains(filter) || self.message.contains(filter))
+    }
+
+    fn colour(&self) -> egui::Color32 {
+        match self.level {
+            Level::ERROR => egui::Color32::RED,
+            Level::WARN => egui::Color32::YELLOW,
+            Level::INFO => egui::Color32::WHITE,
+            Level::DEBUG => egui::Color32::LIGHT_GRAY,
+            _ => egui::Color32::GRAY,
+        }
+    }
+}
+
+struct ConsoleLayer;
+
+impl<S: Subscriber> Layer<S> for ConsoleLayer {
+    fn on_event(&self, event: &bevy::utils::tracing::Event<'_>, _ctx: Context<'_, S>) {
+        let mut message = MessageVisitor::default();
+        event.record(&mut message);
+        let metadata = event.metadata();
+        let Ok(mut lines) = LOG_LINES.lock() else {
+            return;
+        };
+        if lines.len() >= MAX_LOG_LINES {
+            lines.pop_front();
+        }
+        lines.push_back(LogLine {
+            level: *metadata.level(),
+            target: metadata.target().to_string(),
+            message: message.0,
+        });
+    }
+}
+
+/// Formats an event's message followed by any other fields
+#[derive(Default)]
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, "{:?}", value);
+        } else {
+            let _ = write!(self.0, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+#[derive(Resource, Debug)]
+struct ConsoleState {
+    open: bool,
+    input: String,
+    filter: String,
+    max_level: Level,
+}
+
+impl Default for ConsoleState {
+    fn default() -> Self {
+        Self {
+            open: false,
+            input: String::new(),
+            filter: String::new(),
+            max_level: Level::INFO,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DebugGizmo {
+    Lift,
+    SelectionRect,
+    Proximity,
+}
+
+#[derive(Event, Debug, Clone, PartialEq)]
+enum ConsoleCommand {
+    Help,
+    Clear,
+    Spawn {
+        count: usize,
+        floor: i32,
+        destination: i32,
+    },
+    Time(TimeOfDay),
+    Money(f32),
+    Teleport(i32),
+    /// With no state given the gizmo is flipped
+    Gizmo(DebugGizmo, Option<bool>),
+    DumpSpawns,
+}
+
+impl ConsoleCommand {
+    fn parse(input: &str) -> Result<Self, String> {
+        fn arg<T: std::str::FromStr>(args: &[&str], i: usize, name: &str) -> Result<T, String> {
+            let value = args
+                .get(i)
+                .ok_or_else(|| format!("Missing <{}>, see help", name))?;
+            value
+                .parse()
+                .map_err(|_| format!("Invalid <{}>: {}", name, value))
+        }
+
+        let words: Vec<&str> = input.split_whitespace().collect();
+        let Some((command, args)) = words.split_first() else {
+            return Err("Type help for a list of commands".to_string());
+        };
+        match *command {
+            "help" => Ok(Self::Help),
+            "clear" => Ok(Self::Clear),
+            "spawn" => Ok(Self::Spawn {
+                count: arg(args, 0, "count")?,
+                floor: arg(args, 1, "floor")?,
+                destination: arg(args, 2, "destination")?,
+            }),
+            "time" => Ok(Self::Time(arg(args, 0, "HH:MM")?)),
+            "money" => Ok(Self::Money(arg(args, 0, "amount")?)),
+            "tp" => Ok(Self::Teleport(arg(args, 0, "floor")?)),
+            "gizmo" => {
+                let gizmo = match args.first() {
+                    Some(&"lift") => DebugGizmo::Lift,
+                    Some(&"dial") => DebugGizmo::SelectionRect,
+                    Some(&"proximity") => DebugGizmo::Proximity,
+                    _ => return Err("Expected gizmo lift, dial or proximity".to_string()),
+                };
+                let state = match args.get(1) {
+                    None => None,
+                    Some(&"on") => Some(true),
+                    Some(&"off") => Some(false),
+                    Some(other) => return Err(format!("Expected on or off, got {}", other)),
+                };
+                Ok(Self::Gizmo(gizmo, state))
+            }
+            "spawns" => Ok(Self::DumpSpawns),
+            other => Err(format!("Unknown command {}, type help for a list", other)),
+        }
+    }
+}
+
+fn console_toggle_system(
+    inputs: Query<&ActionState<InputAction>>,
+    mut console: ResMut<ConsoleState>,
+) {
+    if let Ok(inputs) = inputs.get_single() {
+        if inputs.just_pressed(&InputAction::ToggleConsole) {
+            console.open = !console.open;
+        }
+    }
+}
+
+/// Keys typed into the console would otherwise also drive the lift, pause, zoom etc., so while
+/// it has the keyboard, every action is consumed until its key is let go
+fn consume_typed_actions_system(
+    mut contexts: EguiContexts,
+    console: Res<ConsoleState>,
+    mut inputs: Query<&mut ActionState<InputAction>>,
+) {
+    if !console.open || !contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    for mut inputs in inputs.iter_mut() {
+        inputs.consume_all();
+    }
+}
+
+fn console_window_system(
+    mut contexts: EguiContexts,
+    mut console: ResMut<ConsoleState>,
+    mut commands: EventWriter<ConsoleCommand>,
+) {
+    if !console.open {
+        return;
+    }
+    let console = &mut *console;
+    let mut open = true;
+    egui::Window::new("Console")
+        .open(&mut open)
+        .default_size([600.0, 300.0])
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_source("console level")
+                    .selected_text(console.max_level.as_str())
+                    .show_ui(ui, |ui| {
+                        for level in LEVELS {
+                            ui.selectable_value(&mut console.max_level, level, level.as_str());
+                        }
+                    });
+                ui.label("Filter");
+                ui.text_edit_singleline(&mut console.filter);
+                if ui.button("Clear").clicked() {
+                    clear_log();
+                }
+            });
+            ui.separator();
+
+            let input_height = ui.spacing().interact_size.y * 2.0;
+            egui::ScrollArea::vertical()
+                .max_height(ui.available_height() - input_height)
+                .stick_to_bottom(true)
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    if let Ok(lines) = LOG_LINES.lock() {
+                        for line in lines
+                            .iter()
+                            .filter(|line| line.matches(console.max_level, &console.filter))
+                        {
+                            ui.label(
+                                egui::RichText::new(format!(
+                                    "{:>5} {}: {}",
+                                    line.level, line.target, line.message
+                                ))
+                                .color(line.colour())
+                                .monospace(),
+                            );
+                        }
+                    }
+                });
+
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut console.input)
+                    .hint_text("Command, try help")
+                    .desired_width(f32::INFINITY),
+            );
+            if response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                let input = std::mem::take(&mut console.input);
+                info!("> {}", input);
+                match ConsoleCommand::parse(&input) {
+                    Ok(ConsoleCommand::Clear) => clear_log(),
+                    Ok(command) => {
+                        commands.send(command);
+                    }
+                    Err(error) => warn!("{}", error),
+                }
+                response.request_focus();
+            }
+        });
+    console.open = open;
+}
+
+fn clear_log() {
+    if let Ok(mut lines) = LOG_LINES.lock() {
+        lines.clear();
+    }
+}
+
+fn run_command_system(
+    mut commands: EventReader<ConsoleCommand>,
+    mut spawn_humans_events: EventWriter<SpawnHumansEvent>,
+    mut game_central_info: ResMut<GameCentralInfo>,
+    mut debug_gizmos: ResMut<DebugGizmos>,
+    mut lift_query: Query<(&mut Transform, &mut LinearVelocity), With<Lift>>,
+    floors: Res<Floors>,
+    lift_limits: Res<LiftLimits>,
+    floor_spawn_manager: Res<FloorSpawnManager>,
+) {
+    let floor_y = |floor: i32| {
+        floors
+            .floor_y_positions
+            .iter()
+            .find(|(floor_num, _)| *floor_num == floor)
+            .map(|(_, y)| *y)
+    };
+    for command in commands.read() {
+        match command {
+            ConsoleCommand::Help => info!("{}", HELP),
+            // Handled straight away by the window
+            ConsoleCommand::Clear => {}
+            ConsoleCommand::Spawn {
+                count,
+                floor,
+                destination,
+            } => {
+                if floor_y(*floor).is_none() || floor_y(*destination).is_none() {
+                    warn!("No such floor");
+                    continue;
+                }
+                for _ in 0..*count {
+                    spawn_humans_events.send(SpawnHumansEvent::new(
+                        FloorNum(*floor),
+                        FloorNum(*destination),
+                    ));
+                }
+                info!(
+                    "Spawned {} humans at {} for {}",
+                    count,
+                    floor_num_pretty_str(*floor),
+                    floor_num_pretty_str(*destination)
+                );
+            }
+            ConsoleCommand::Time(time) => {
+                game_central_info.time.set_time_of_day(*time);
+                info!(
+                    "Clock set to {}",
+                    game_central_info.time.to_game_time_of_day()
+                );
+            }
+            ConsoleCommand::Money(amount) => {
+                game_central_info.add_money(*amount);
+                info!("Money now ${:.2}", game_central_info.money());
+            }
+            ConsoleCommand::Teleport(floor) => {
+                let Some(y) = floor_y(*floor) else {
+                    warn!("No such floor");
+                    continue;
+                };
+                for (mut transform, mut velocity) in lift_query.iter_mut() {
+                    transform.translation.y = y.clamp(lift_limits.min, lift_limits.max);
+                    velocity.velocity = 0.0;
+                }
+                info!("Teleported the lift to {}", floor_num_pretty_str(*floor));
+            }
+            ConsoleCommand::Gizmo(gizmo, state) => {
+                let enabled = match gizmo {
+                    DebugGizmo::Lift => &mut debug_gizmos.lift,
+                    DebugGizmo::SelectionRect => &mut debug_gizmos.selection_rect,
+                    DebugGizmo::Proximity => &mut debug_gizmos.proximity,
+                };
+                *enabled = state.unwrap_or(!*enabled);
+                info!("{:?} gizmo {}", gizmo, if *enabled { "on" } else { "off" });
+            }
+            ConsoleCommand::DumpSpawns => info!("{:#?}", *floor_spawn_manager),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(
+            ConsoleCommand::parse("spawn 3 0 5"),
+            Ok(ConsoleCommand::Spawn {
+                count: 3,
+                floor: 0,
+                destination: 5
+            })
+        );
+        assert_eq!(
+            ConsoleCommand::parse("  time 09:30 "),
+            Ok(ConsoleCommand::Time(TimeOfDay::new(9, 30)))
+        );
+        assert_eq!(
+            ConsoleCommand::parse("money -2.5"),
+            Ok(ConsoleCommand::Money(-2.5))
+        );
+        assert_eq!(
+            ConsoleCommand::parse("gizmo dial off"),
+            Ok(ConsoleCommand::Gizmo(
+                DebugGizmo::SelectionRect,
+                Some(false)
+            ))
+        );
+        assert_eq!(
+            ConsoleCommand::parse("gizmo lift"),
+            Ok(ConsoleCommand::Gizmo(DebugGizmo::Lift, None))
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(ConsoleCommand::parse("").is_err());
+        assert!(ConsoleCommand::parse("fly").is_err());
+        assert!(ConsoleCommand::parse("spawn 3 0").is_err());
+        assert!(ConsoleCommand::parse("tp up").is_err());
+        assert!(ConsoleCommand::parse("time 25:00").is_err());
+        assert!(ConsoleCommand::parse("gizmo lift maybe").is_err());
+    }
+
+    #[test]
+    fn test_log_line_filter() {
+        let line = LogLine {
+            level: Level::DEBUG,
+            target: "going_up::game::floors".to_string(),
+            message: "Sending spawn".to_string(),
+        };
+        assert!(!line.matches(Level::INFO, ""));
+        assert!(line.matches(Level::DEBUG, "floors"));
+        assert!(line.matches(Level::TRACE, "spawn"));
+        assert!(!line.matches(Level::TRACE, "lift"));
+    }
+}
@@ -0,0 +1,301 @@
+//! Developer tooling, only built with the `debug-tools` feature: the world inspector, debug
+//! gizmos and text, the velocity plots, the raw held humans window and the [console]. All but
+//! the console are shown or hidden together with [InputAction::ToggleDebugUi]
+
+pub mod console;
+mod plots;
+
+use crate::game::floors::{floor_num_pretty_str, FloorLatchYPositions, ShaftCentreX};
+use crate::game::game::{DayScoped, FloorProximity, FloorProximitySensor, Lift, LiftMode, LIFT_Z};
+use crate::game::lift::{
+    patience_rgb, patience_warning_visible, LiftHumanStore, StoredHuman, LOW_PATIENCE,
+};
+use crate::game::speed_selector::{MouseSelectionRect, OverlayGizmos, SpeedDial};
+use crate::game::ui;
+use crate::input_action::InputAction;
+use crate::loading::TextureAssets;
+use crate::settings::Settings;
+use crate::ui_widgets::{fill_bar, BarOrientation};
+use crate::GameState;
+use bevy::prelude::*;
+use bevy_egui::egui::{Align2, Color32, RichText};
+use bevy_egui::{egui, EguiContexts};
+use bevy_inspector_egui::quick::WorldInspectorPlugin;
+use leafwing_input_manager::prelude::*;
+use std::f32::consts::TAU;
+
+pub struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            WorldInspectorPlugin::new().run_if(debug_ui_visible),
+            console::ConsolePlugin,
+        ))
+        .add_systems(OnEnter(GameState::PlayingDay), spawn_lift_mode_text)
+        .add_systems(
+            Update,
+            (
+                debug_ui_toggle_system,
+                lift_mode_text_visibility_system,
+                (
+                    lift_mode_text_system,
+                    held_humans_window_system,
+                    lift_gizmo_system.run_if(|gizmos: Res<DebugGizmos>| gizmos.lift),
+                    proximity_timer_gizmo_system
+                        .run_if(|gizmos: Res<DebugGizmos>| gizmos.proximity),
+                    mouse_selection_rect_gizmo_system
+                        .run_if(|gizmos: Res<DebugGizmos>| gizmos.selection_rect),
+                )
+                    .run_if(debug_ui_visible),
+            )
+                .chain()
+                .run_if(in_state(GameState::PlayingDay)),
+        )
+        .init_resource::<DebugUiVisible>()
+        .init_resource::<DebugGizmos>();
+        plots::add(app);
+    }
+}
+
+/// Whether the debug tools are shown, they all start hidden
+#[derive(Resource, Debug, Default)]
+pub struct DebugUiVisible(pub bool);
+
+pub fn debug_ui_visible(debug_ui: Res<DebugUiVisible>) -> bool {
+    debug_ui.0
+}
+
+/// Which debug gizmos are drawn while the debug tools are shown, toggled from the [console]
+#[derive(Resource, Debug)]
+pub struct DebugGizmos {
+    /// Floor latch positions and the lift's centre
+    pub lift: bool,
+    /// Where the speed dial can be grabbed
+    pub selection_rect: bool,
+    /// The arc filling up while the lift waits at a floor
+    pub proximity: bool,
+}
+
+impl Default for DebugGizmos {
+    fn default() -> Self {
+        Self {
+            lift: true,
+            selection_rect: true,
+            proximity: true,
+        }
+    }
+}
+
+fn debug_ui_toggle_system(
+    inputs: Query<&ActionState<InputAction>>,
+    mut debug_ui: ResMut<DebugUiVisible>,
+) {
+    if let Ok(inputs) = inputs.get_single() {
+        if inputs.just_pressed(&InputAction::ToggleDebugUi) {
+            debug_ui.0 = !debug_ui.0;
+        }
+    }
+}
+
+impl LiftMode {
+    fn as_str(&self) -> &str {
+        match self {
+            Self::Free => "free",
+            Self::Opening => "opening",
+            Self::Open => "open",
+            Self::Closing => "closing",
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+struct LiftModeDebugText;
+
+fn spawn_lift_mode_text(mut commands: Commands) {
+    commands
+        .spawn(Text2dBundle {
+            text: Text::from_section("", TextStyle::default()),
+            ..default()
+        })
+        .insert(LiftModeDebugText)
+        .insert(DayScoped);
+}
+
+fn lift_mode_text_visibility_system(
+    debug_ui: Res<DebugUiVisible>,
+    mut query: Query<&mut Visibility, With<LiftModeDebugText>>,
+) {
+    let shown = match debug_ui.0 {
+        true => Visibility::Inherited,
+        false => Visibility::Hidden,
+    };
+    for mut visibility in query.iter_mut() {
+        if *visibility != shown {
+            *visibility = shown;
+        }
+    }
+}
+
+fn lift_mode_text_system(
+    lift_query: Query<(&GlobalTransform, &LiftMode), With<Lift>>,
+    mut text_query: Query<(&mut Transform, &mut Text), (Without<Lift>, With<LiftModeDebugText>)>,
+) {
+    for (lift_transform, lift_mode) in lift_query.iter() {
+        for (mut text_transform, mut text) in text_query.iter_mut() {
+            text_transform.translation = lift_transform.translation();
+            text_transform.translation.y += 10.0;
+            text_transform.translation.z = LIFT_Z + 1.0;
+            text.sections[0].value = lift_mode.as_str().to_string();
+        }
+    }
+}
+
+fn lift_gizmo_system(
+    floor_latch_ypositions: Res<FloorLatchYPositions>,
+    shaft_centre_x: Res<ShaftCentreX>,
+    lift_query: Query<&Transform, With<Lift>>,
+    mut gizmos: Gizmos,
+) {
+    let shaft_centre_x = shaft_centre_x.0;
+    for y in floor_latch_ypositions.0.iter() {
+        gizmos.line_2d(
+            Vec2::new(shaft_centre_x - 10.0, *y),
+            Vec2::new(shaft_centre_x + 10.0, *y),
+            Color::BLUE,
+        );
+    }
+
+    for lift in lift_query.iter() {
+        gizmos.circle_2d(lift.translation.truncate(), 10.0, Color::RED);
+    }
+}
+
+fn proximity_timer_gizmo_system(
+    query: Query<(Option<&FloorProximity>, &FloorProximitySensor, &Transform)>,
+    mut gizmos: Gizmos,
+) {
+    for (proximity, sensor, transform) in query.iter() {
+        if let Some(proximity) = proximity {
+            let fill_pct = proximity.time_in_proximity.elapsed().as_secs_f32()
+                / sensor.floor_timer_duration.as_secs_f32();
+            gizmos.arc_2d(
+                transform.translation.truncate(),
+                0.0,
+                TAU * fill_pct,
+                15.0,
+                Color::BLUE,
+            );
+        }
+    }
+}
+
+fn mouse_selection_rect_gizmo_system(
+    selector_query: Query<&MouseSelectionRect, With<SpeedDial>>,
+    mut gizmos: Gizmos<OverlayGizmos>,
+) {
+    for selector_rect in selector_query.iter() {
+        gizmos.rect_2d(
+            selector_rect.world_rect.center(),
+            0.0,
+            selector_rect.world_rect.half_size(),
+            Color::RED,
+        );
+    }
+}
+
+fn held_human_ui(
+    ui: &mut egui::Ui,
+    human: Option<&StoredHuman>,
+    icon: egui::TextureId,
+    orientation: BarOrientation,
+    elapsed_secs: f32,
+) {
+    let text_color = Color32::WHITE;
+    let size = 24.0;
+
+    ui.horizontal(|ui| {
+        ui.add(egui::Image::new(egui::load::SizedTexture::new(
+            icon,
+            [20., 30.],
+        )));
+        let Some(human) = human else {
+            return;
+        };
+        let fraction = human.patience_fraction();
+        let [r, g, b] = patience_rgb(fraction);
+        ui.vertical(|ui| {
+            ui.label(
+                RichText::new(floor_num_pretty_str(human.destination_floor()))
+                    .color(text_color)
+                    .size(size),
+            );
+            fill_bar(
+                ui,
+                Color32::BLACK,
+                egui::Rgba::from_rgb(r, g, b).into(),
+                fraction,
+                orientation,
+                2.0,
+            );
+        });
+        if patience_warning_visible(fraction, elapsed_secs) {
+            ui.label(RichText::new("!").color(Color32::RED).size(size).strong());
+        }
+    });
+}
+
+fn held_humans_window_system(
+    humans: Res<LiftHumanStore>,
+    mut contexts: EguiContexts,
+    texture_assets: Res<TextureAssets>,
+    settings: Res<Settings>,
+    time: Res<Time<Real>>,
+) {
+    // Images are only registered once, later calls just look up the id
+    let icons: Vec<_> = humans
+        .passengers()
+        .map(|human| {
+            let texture = match human {
+                Some(human) => human.kind().icon(&texture_assets),
+                None => &texture_assets.human_icon_off,
+            };
+            contexts.add_image(texture.clone_weak())
+        })
+        .collect();
+    let elapsed_secs = time.elapsed_seconds();
+    let frame = ui::default_frame();
+    let num_columns = 3;
+    let ctx = contexts.ctx_mut();
+    egui::Window::new("Held Humans")
+        .movable(false)
+        .anchor(Align2::RIGHT_TOP, egui::Vec2::ZERO)
+        .title_bar(false)
+        .frame(frame)
+        .show(ctx, |ui| {
+            egui::Grid::new("held human slots")
+                .num_columns(num_columns)
+                .show(ui, |ui| {
+                    for (i, (human, icon)) in humans.passengers().zip(icons).enumerate() {
+                        if i % num_columns == 0 {
+                            ui.end_row();
+                        }
+                        held_human_ui(
+                            ui,
+                            human,
+                            icon,
+                            settings.patience_bar_orientation,
+                            elapsed_secs,
+                        );
+                    }
+                });
+        });
+    // Keep the low patience warnings flashing without waiting on input
+    if humans
+        .passengers()
+        .flatten()
+        .any(|human| human.patience_fraction() < LOW_PATIENCE)
+    {
+        ctx.request_repaint();
+    }
+}
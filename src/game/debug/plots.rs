@@ -0,0 +1,202 @@
+//! Plots of the lift's recent velocity, acceleration and jerk
+
+use crate::game::debug::debug_ui_visible;
+use crate::game::game::{
+    AccelerationLog, DayScopedResourceExt, JerkLog, ObservedVelocityLog, VelocityLog,
+};
+use crate::history_store::HistoryStore;
+use crate::GameState;
+use bevy::prelude::*;
+use bevy_egui::egui::Color32;
+use bevy_egui::{egui, EguiContexts};
+use egui_plot::{Line, Plot, PlotPoints};
+
+pub fn add(app: &mut App) {
+    app.add_systems(
+        Update,
+        (recalculate_plot_points, show_ui)
+            .run_if(in_state(GameState::PlayingDay))
+            .run_if(debug_ui_visible),
+    )
+    .init_resource::<ShowUiState>()
+    .init_day_resource::<TrueVelocityPlotPoints>()
+    .init_day_resource::<ObservedVelocityPlotPoints>()
+    .init_day_resource::<AccelerationPlotPoints>()
+    .init_day_resource::<JerkPlotPoints>();
+}
+
+#[derive(Resource)]
+struct ShowUiState {
+    show_velocity: bool,
+    show_acceleration: bool,
+    show_smooth_acceleration: bool,
+    show_observed_velocity: bool,
+    show_jerk: bool,
+    /// How many seconds back from the latest sample the plot shows
+    plot_window_seconds: f32,
+}
+impl Default for ShowUiState {
+    fn default() -> Self {
+        Self {
+            show_velocity: true,
+            show_acceleration: true,
+            show_smooth_acceleration: true,
+            show_observed_velocity: true,
+            // Off by default as it dwarfs everything else
+            show_jerk: false,
+            plot_window_seconds: 8.0,
+        }
+    }
+}
+
+fn show_ui(
+    mut contexts: EguiContexts,
+    target_velocity_plot_points: Res<TrueVelocityPlotPoints>,
+    observed_velocity_plot_points: Res<ObservedVelocityPlotPoints>,
+    acceleration_plot_points: Res<AccelerationPlotPoints>,
+    jerk_plot_points: Res<JerkPlotPoints>,
+    mut show_state: ResMut<ShowUiState>,
+) {
+    egui::Window::new("Ui").show(contexts.ctx_mut(), |ui| {
+        ui.checkbox(&mut show_state.show_velocity, "Show Velocity");
+        ui.checkbox(&mut show_state.show_acceleration, "Show Acceleration");
+        ui.checkbox(
+            &mut show_state.show_smooth_acceleration,
+            "Show Smooth Acceleration",
+        );
+        ui.checkbox(&mut show_state.show_observed_velocity, "Show Obs Velocity");
+        ui.checkbox(&mut show_state.show_jerk, "Show Jerk");
+        ui.add(
+            egui::Slider::new(
+                &mut show_state.plot_window_seconds,
+                MIN_PLOT_WINDOW_SECONDS..=MAX_PLOT_WINDOW_SECONDS,
+            )
+            .logarithmic(true)
+            .suffix("s")
+            .text("Time Window"),
+        );
+        Plot::new("Plot").view_aspect(2.0).show(ui, |ui| {
+            if show_state.show_velocity {
+                let velocity_points = PlotPoints::new(target_velocity_plot_points.0.clone());
+                ui.line(
+                    Line::new(velocity_points)
+                        .name("Velocity")
+                        .color(Color32::GREEN),
+                );
+            }
+            if show_state.show_acceleration {
+                let accel_points = PlotPoints::new(acceleration_plot_points.0.clone());
+                ui.line(
+                    Line::new(accel_points)
+                        .name("Acceleration")
+                        .color(Color32::RED),
+                );
+            }
+            if show_state.show_smooth_acceleration {
+                let accel_points = {
+                    let mut prev: Option<[f64; 2]> = None;
+                    let points: Vec<[f64; 2]> = acceleration_plot_points
+                        .0
+                        .iter()
+                        .map(|point| match prev {
+                            Some(prev_point) => {
+                                let x = (prev_point[0] + point[0]) / 2.0;
+                                let y = (prev_point[1] + point[1]) / 2.0;
+                                prev = Some(*point);
+                                [x, y]
+                            }
+                            None => {
+                                prev = Some(*point);
+                                *point
+                            }
+                        })
+                        .collect();
+                    PlotPoints::new(points)
+                };
+                ui.line(
+                    Line::new(accel_points)
+                        .name("Smoothed Acceleration")
+                        .color(Color32::YELLOW),
+                );
+            }
+            if show_state.show_observed_velocity {
+                let obs_velocity_points = PlotPoints::new(observed_velocity_plot_points.0.clone());
+                ui.line(
+                    Line::new(obs_velocity_points)
+                        .name("Observed Velocity")
+                        .color(Color32::DARK_GREEN),
+                );
+            }
+            if show_state.show_jerk {
+                let jerk_points = PlotPoints::new(jerk_plot_points.0.clone());
+                ui.line(
+                    Line::new(jerk_points)
+                        .name("Jerk")
+                        .color(Color32::LIGHT_BLUE),
+                );
+            }
+        });
+    });
+}
+
+const MIN_PLOT_WINDOW_SECONDS: f32 = 2.0;
+const MAX_PLOT_WINDOW_SECONDS: f32 = 3600.0;
+
+/// Points from the last [window_seconds] of [log]. Served from the high-res primary tier if it
+/// covers the whole window, otherwise from the down-sampled storage tier merged in front of it
+fn windowed_plot_points(log: &HistoryStore<(f32, f32)>, window_seconds: f32) -> Vec<[f64; 2]> {
+    let (Some((oldest_primary, _)), Some((latest, _))) =
+        (log.iter_primary().next(), log.iter_primary().last())
+    else {
+        return vec![];
+    };
+    let window_start = latest - window_seconds;
+    let to_point = |(time, val): &(f32, f32)| [*time as f64, *val as f64];
+    if window_start >= *oldest_primary {
+        log.iter_primary()
+            .filter(|(time, _)| *time >= window_start)
+            .map(to_point)
+            .collect()
+    } else {
+        log.iter_merged()
+            .filter(|(time, _)| *time >= window_start)
+            .map(to_point)
+            .collect()
+    }
+}
+
+fn recalculate_plot_points(
+    show_state: Res<ShowUiState>,
+    velocity_log: Res<VelocityLog>,
+    observed_velocity_log: Res<ObservedVelocityLog>,
+    acceleration_log: Res<AccelerationLog>,
+    jerk_log: Res<JerkLog>,
+    mut velocity_plot: ResMut<TrueVelocityPlotPoints>,
+    mut obs_velocity_plot: ResMut<ObservedVelocityPlotPoints>,
+    mut acceleration_plot: ResMut<AccelerationPlotPoints>,
+    mut jerk_plot: ResMut<JerkPlotPoints>,
+    mut count: Local<usize>,
+) {
+    // This means plot update is framerate linked, but meh
+    *count = (*count + 1usize) % 4;
+    if *count == 0 {
+        velocity_plot.0 = windowed_plot_points(&velocity_log.0, show_state.plot_window_seconds);
+    } else if *count == 1 {
+        acceleration_plot.0 =
+            windowed_plot_points(&acceleration_log.0, show_state.plot_window_seconds);
+    } else if *count == 2 {
+        obs_velocity_plot.0 =
+            windowed_plot_points(&observed_velocity_log.0, show_state.plot_window_seconds);
+    } else if *count == 3 {
+        jerk_plot.0 = windowed_plot_points(&jerk_log.0, show_state.plot_window_seconds);
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+struct TrueVelocityPlotPoints(Vec<[f64; 2]>);
+#[derive(Resource, Debug, Default)]
+struct ObservedVelocityPlotPoints(Vec<[f64; 2]>);
+#[derive(Resource, Debug, Default)]
+struct AccelerationPlotPoints(Vec<[f64; 2]>);
+#[derive(Resource, Debug, Default)]
+struct JerkPlotPoints(Vec<[f64; 2]>);
@@ -0,0 +1,467 @@
+//! Endless mode: rather than a single day, a run of back to back shifts with ever more traffic.
+//! Spawn rates ramp up with the time the run has gone on, and between shifts the building grows
+//! a floor. The run is over once more than [MAX_ABANDONMENTS] humans have run out of patience,
+//! and the best runs are kept in [HIGH_SCORES_PATH] along with their seeds, so they can be
+//! played again.
+//!
+//! Each shift is its own [GameState::PlayingDay], seeded from the run's seed, so a run plays out
+//! the same way given the same inputs. Runs aren't recorded as replays, as those can only hold
+//! a single day.
+
+use crate::game::campaign::ActiveCampaignDay;
+use crate::game::floors;
+use crate::game::game::DaySimulationSet;
+use crate::game::game_clock::GameTimeConfig;
+use crate::game::lift::LiftHumanStore;
+use crate::game::seed::DaySeed;
+use crate::game::spawn_simulation::FloorSpawnManager;
+use crate::game::telemetry::{DayEndedEvent, SessionLog};
+use crate::game::ui::{self, GameCentralInfo};
+use crate::game::world_gen::{Class, Level, LevelKind};
+use crate::storage;
+use crate::GameState;
+use bevy::prelude::*;
+use bevy_egui::egui::{Align2, Color32, RichText};
+use bevy_egui::{egui, EguiContexts};
+
+const HIGH_SCORES_PATH: &str = "high_scores.cfg";
+const MAX_HIGH_SCORES: usize = 10;
+/// More than this many abandonments over the whole run ends it
+pub const MAX_ABANDONMENTS: usize = 10;
+const START_FLOOR_COUNT: usize = 5;
+const MAX_FLOOR_COUNT: usize = 30;
+/// How much the spawn rates grow per minute of the run, on top of the starting 1.0
+const RAMP_PER_MINUTE: f32 = 0.25;
+
+pub struct EndlessPlugin;
+
+impl Plugin for EndlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                ramp_system.before(floors::floor_spawn_process_system),
+                game_over_system.after(LiftHumanStore::update_system),
+            )
+                .in_set(DaySimulationSet::World)
+                .run_if(resource_exists::<EndlessRun>),
+        )
+        .add_systems(
+            Update,
+            (next_shift_system, run_window_system)
+                .run_if(in_state(GameState::PlayingDay))
+                .run_if(resource_exists::<EndlessRun>),
+        )
+        .add_systems(OnEnter(GameState::GameOver), record_high_score_system)
+        .add_systems(Update, game_over_menu.run_if(in_state(GameState::GameOver)))
+        .insert_resource(HighScores::load_or_default());
+    }
+}
+
+/// Traffic multiplier once the run has gone on for [run_secs]
+pub fn strength_scale(run_secs: f32) -> f32 {
+    1.0 + RAMP_PER_MINUTE * run_secs / 60.0
+}
+
+/// Present while an endless run is being played, and on its [GameState::GameOver] screen
+#[derive(Resource, Debug, Clone)]
+pub struct EndlessRun {
+    pub seed: u64,
+    /// Counting from 0
+    shift: u32,
+    floor_count: usize,
+    /// Totals over the finished shifts, the current shift's are in its [SessionLog]
+    past_secs: f32,
+    past_deliveries: usize,
+    past_abandonments: usize,
+    over: bool,
+    /// Where the run placed in the [HighScores], once it's over
+    rank: Option<usize>,
+}
+
+impl EndlessRun {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            shift: 0,
+            floor_count: START_FLOOR_COUNT,
+            past_secs: 0.0,
+            past_deliveries: 0,
+            past_abandonments: 0,
+            over: false,
+            rank: None,
+        }
+    }
+
+    /// Every shift is a different day, but the same for every run from [EndlessRun::seed]
+    fn shift_seed(&self) -> u64 {
+        self.seed.wrapping_add(self.shift as u64)
+    }
+
+    fn level(&self) -> Level {
+        Level::with_floor_count(LevelKind::Hotel, Class::Two, self.floor_count)
+    }
+
+    fn run_secs(&self, info: &GameCentralInfo) -> f32 {
+        self.past_secs + info.time.elapsed_secs()
+    }
+
+    fn deliveries(&self, session_log: &SessionLog) -> usize {
+        self.past_deliveries + session_log.deliveries()
+    }
+
+    fn abandonments(&self, session_log: &SessionLog) -> usize {
+        self.past_abandonments + session_log.abandonments()
+    }
+
+    /// Fold the current shift into the run's totals
+    fn finish_shift(&mut self, session_log: &SessionLog, info: &GameCentralInfo) {
+        self.past_secs = self.run_secs(info);
+        self.past_deliveries = self.deliveries(session_log);
+        self.past_abandonments = self.abandonments(session_log);
+    }
+
+    fn start_next_shift(&mut self) {
+        self.shift += 1;
+        self.floor_count = (self.floor_count + 1).min(MAX_FLOOR_COUNT);
+    }
+
+    fn high_score(&self) -> HighScore {
+        HighScore {
+            deliveries: self.past_deliveries,
+            shifts: self.shift + 1,
+            seed: self.seed,
+        }
+    }
+}
+
+/// Set up the next [GameState::PlayingDay] to be the first shift of an endless run from [seed]
+pub fn start_endless_run(
+    seed: u64,
+    commands: &mut Commands,
+    day_seed: &mut DaySeed,
+    time_config: &mut GameTimeConfig,
+    level: &mut Level,
+) {
+    info!("Starting endless run (seed {})", seed);
+    let run = EndlessRun::new(seed);
+    day_seed.0 = run.shift_seed();
+    *time_config = GameTimeConfig::default();
+    *level = run.level();
+    commands.insert_resource(run);
+    commands.remove_resource::<ActiveCampaignDay>();
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HighScore {
+    pub deliveries: usize,
+    /// Shifts started, including the one the run ended in
+    pub shifts: u32,
+    pub seed: u64,
+}
+
+impl HighScore {
+    fn of_cfg_line(line: &str) -> Option<Self> {
+        let mut fields = line.split(',').map(str::trim);
+        let score = Self {
+            deliveries: fields.next()?.parse().ok()?,
+            shifts: fields.next()?.parse().ok()?,
+            seed: fields.next()?.parse().ok()?,
+        };
+        fields.next().is_none().then_some(score)
+    }
+}
+
+/// The best [MAX_HIGH_SCORES] runs, best first
+#[derive(Resource, Debug, Default, Clone, PartialEq)]
+pub struct HighScores {
+    scores: Vec<HighScore>,
+}
+
+impl HighScores {
+    pub fn iter(&self) -> impl Iterator<Item = &HighScore> {
+        self.scores.iter()
+    }
+
+    /// Returns where [score] placed, or None if it didn't make the table. Ties go to the run
+    /// which got there first
+    pub fn insert(&mut self, score: HighScore) -> Option<usize> {
+        let rank = self
+            .scores
+            .iter()
+            .position(|other| score.deliveries > other.deliveries)
+            .unwrap_or(self.scores.len());
+        if rank >= MAX_HIGH_SCORES {
+            return None;
+        }
+        self.scores.insert(rank, score);
+        self.scores.truncate(MAX_HIGH_SCORES);
+        Some(rank)
+    }
+
+    fn to_cfg(&self) -> String {
+        self.scores
+            .iter()
+            .map(|score| format!("{},{},{}\n", score.deliveries, score.shifts, score.seed))
+            .collect()
+    }
+
+    /// Unknown lines are skipped, as with the keybindings
+    fn of_cfg(cfg: &str) -> Self {
+        let mut high_scores = Self::default();
+        for line in cfg.lines() {
+            match HighScore::of_cfg_line(line) {
+                Some(score) => {
+                    high_scores.insert(score);
+                }
+                None => warn!("Ignoring unknown high score: {}", line),
+            }
+        }
+        high_scores
+    }
+
+    pub fn load_or_default() -> Self {
+        match storage::read_to_string(HIGH_SCORES_PATH) {
+            Ok(cfg) => Self::of_cfg(&cfg),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) {
+        if let Err(error) = storage::write(HIGH_SCORES_PATH, &self.to_cfg()) {
+            warn!("Failed to save high scores: {:?}", error);
+        }
+    }
+}
+
+fn ramp_system(
+    run: Res<EndlessRun>,
+    info: Res<GameCentralInfo>,
+    mut floor_spawn_manager: ResMut<FloorSpawnManager>,
+) {
+    floor_spawn_manager.set_strength_scale(strength_scale(run.run_secs(&info)));
+}
+
+fn game_over_system(
+    mut run: ResMut<EndlessRun>,
+    session_log: Res<SessionLog>,
+    info: Res<GameCentralInfo>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if run.over || run.abandonments(&session_log) <= MAX_ABANDONMENTS {
+        return;
+    }
+    run.finish_shift(&session_log, &info);
+    run.over = true;
+    info!("Endless run over: {:?}", run.high_score());
+    next_state.set(GameState::GameOver);
+}
+
+/// Grow the building and carry on with the next shift once the clock runs out
+fn next_shift_system(
+    mut day_ended_events: EventReader<DayEndedEvent>,
+    mut run: ResMut<EndlessRun>,
+    session_log: Res<SessionLog>,
+    info: Res<GameCentralInfo>,
+    mut day_seed: ResMut<DaySeed>,
+    mut level: ResMut<Level>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if day_ended_events.read().count() == 0 || run.over {
+        return;
+    }
+    run.finish_shift(&session_log, &info);
+    run.start_next_shift();
+    info!(
+        "Starting shift {} with {} floors",
+        run.shift + 1,
+        run.floor_count
+    );
+    day_seed.0 = run.shift_seed();
+    *level = run.level();
+    next_state.set(GameState::RestartingDay);
+}
+
+fn run_window_system(
+    mut contexts: EguiContexts,
+    run: Res<EndlessRun>,
+    session_log: Res<SessionLog>,
+    info: Res<GameCentralInfo>,
+) {
+    let text_color = Color32::WHITE;
+    let abandonments = run.abandonments(&session_log);
+    let abandonments_color = if abandonments >= MAX_ABANDONMENTS {
+        Color32::RED
+    } else {
+        text_color
+    };
+    egui::Window::new("Endless")
+        .movable(false)
+        .anchor(Align2::LEFT_BOTTOM, egui::Vec2::ZERO)
+        .title_bar(false)
+        .frame(ui::default_frame())
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label(
+                RichText::new(format!(
+                    "Shift {} ({} floors)",
+                    run.shift + 1,
+                    run.floor_count
+                ))
+                .color(text_color)
+                .strong(),
+            );
+            ui.label(
+                RichText::new(format!(
+                    "Traffic x{:.2}",
+                    strength_scale(run.run_secs(&info))
+                ))
+                .color(text_color),
+            );
+            ui.label(
+                RichText::new(format!("Delivered {}", run.deliveries(&session_log)))
+                    .color(text_color),
+            );
+            ui.label(
+                RichText::new(format!("Abandoned {} / {}", abandonments, MAX_ABANDONMENTS))
+                    .color(abandonments_color),
+            );
+        });
+}
+
+fn record_high_score_system(mut run: ResMut<EndlessRun>, mut high_scores: ResMut<HighScores>) {
+    run.rank = high_scores.insert(run.high_score());
+    if run.rank.is_some() {
+        high_scores.save();
+    }
+}
+
+/// The table of [HighScores], with [highlight] picked out. With [playable] each run gets a
+/// button to play its seed again, returning the seed if one was clicked
+pub fn high_scores_ui(
+    ui: &mut egui::Ui,
+    high_scores: &HighScores,
+    highlight: Option<usize>,
+    playable: bool,
+) -> Option<u64> {
+    let mut play_seed = None;
+    egui::Grid::new("high scores")
+        .num_columns(if playable { 5 } else { 4 })
+        .striped(true)
+        .show(ui, |ui| {
+            for heading in ["", "Delivered", "Shifts", "Seed"] {
+                ui.label(RichText::new(heading).strong());
+            }
+            ui.end_row();
+            for (i, score) in high_scores.iter().enumerate() {
+                let color = if Some(i) == highlight {
+                    Color32::GOLD
+                } else {
+                    ui.visuals().text_color()
+                };
+                for value in [
+                    format!("{}.", i + 1),
+                    score.deliveries.to_string(),
+                    score.shifts.to_string(),
+                    score.seed.to_string(),
+                ] {
+                    ui.label(RichText::new(value).color(color));
+                }
+                if playable && ui.button("Play").clicked() {
+                    play_seed = Some(score.seed);
+                }
+                ui.end_row();
+            }
+        });
+    play_seed
+}
+
+fn game_over_menu(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    run: Res<EndlessRun>,
+    high_scores: Res<HighScores>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut day_seed: ResMut<DaySeed>,
+    mut time_config: ResMut<GameTimeConfig>,
+    mut level: ResMut<Level>,
+) {
+    egui::Window::new("Game Over")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.vertical_centered(|ui| {
+                let score = run.high_score();
+                ui.label(format!(
+                    "Too many people gave up. You delivered {} people over {} shifts",
+                    score.deliveries, score.shifts
+                ));
+                if let Some(rank) = run.rank {
+                    ui.label(RichText::new(format!("New high score, #{}", rank + 1)).strong());
+                }
+                high_scores_ui(ui, &high_scores, run.rank, false);
+                ui.style_mut().text_styles.insert(
+                    egui::TextStyle::Button,
+                    egui::FontId::new(23.0, egui::FontFamily::Proportional),
+                );
+                if ui.button("Try Again").clicked() {
+                    start_endless_run(
+                        run.seed,
+                        &mut commands,
+                        &mut day_seed,
+                        &mut time_config,
+                        &mut level,
+                    );
+                    next_state.set(GameState::PlayingDay);
+                }
+                if ui.button("Back to Menu").clicked() {
+                    commands.remove_resource::<EndlessRun>();
+                    next_state.set(GameState::PlayingMenu);
+                }
+            });
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(deliveries: usize, seed: u64) -> HighScore {
+        HighScore {
+            deliveries,
+            shifts: 1,
+            seed,
+        }
+    }
+
+    #[test]
+    fn test_strength_scale() {
+        assert_eq!(strength_scale(0.0), 1.0);
+        assert_eq!(strength_scale(120.0), 1.5);
+    }
+
+    #[test]
+    fn test_high_scores() {
+        let mut high_scores = HighScores::default();
+        assert_eq!(high_scores.insert(score(10, 1)), Some(0));
+        assert_eq!(high_scores.insert(score(20, 2)), Some(0));
+        assert_eq!(high_scores.insert(score(10, 3)), Some(2));
+        for i in 0..MAX_HIGH_SCORES {
+            high_scores.insert(score(30, i as u64));
+        }
+        assert_eq!(high_scores.iter().count(), MAX_HIGH_SCORES);
+        assert_eq!(high_scores.insert(score(5, 4)), None);
+        assert_eq!(HighScores::of_cfg(&high_scores.to_cfg()), high_scores);
+    }
+
+    #[test]
+    fn test_shifts() {
+        let mut run = EndlessRun::new(7);
+        assert_eq!(run.shift_seed(), 7);
+        for _ in 0..MAX_FLOOR_COUNT {
+            run.start_next_shift();
+        }
+        assert_eq!(run.shift_seed(), 7 + MAX_FLOOR_COUNT as u64);
+        assert_eq!(run.level().floor_count(), MAX_FLOOR_COUNT);
+    }
+}
@@ -0,0 +1,260 @@
+use crate::camera::MainCamera;
+use crate::game::floors::{floor_num_pretty_str, FloorNum, Floors, LiftLimits, ShaftCentreX};
+use crate::game::game::{DayScopedResourceExt, FloorProximity, Lift, LinearVelocity};
+use crate::game::human_store::HumanStore;
+use crate::game::minimap::MinimapHovered;
+use crate::game::motion_planning::{self, MotionLimits};
+use crate::game::speed_selector::{DialTouch, SelectionEnabled, TargetVelocity};
+use crate::settings::Settings;
+use crate::{GameState, InputAction, PauseState};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+use leafwing_input_manager::action_state::ActionState;
+use std::collections::VecDeque;
+
+/// Half the width of a floor tile, clicks within this of the shaft centre or a vestibule (and
+/// a floor's latch position) count as being on it
+const TILE_HALF_WIDTH: f32 = 30.0;
+/// Touches which move further than this (in logical pixels) are drags, not taps
+const TAP_MAX_MOVEMENT: f32 = 10.0;
+
+pub struct FloorSelectPlugin;
+
+impl Plugin for FloorSelectPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                floor_tap_system,
+                floor_click_system,
+                plan_stops_system
+                    .after(floor_tap_system)
+                    .after(floor_click_system),
+                complete_stops_system,
+                planned_stops_gizmo_system,
+                planned_stops_ui_system,
+            )
+                .run_if(in_state(GameState::PlayingDay))
+                .run_if(in_state(PauseState::Running)),
+        )
+        .init_day_resource::<PlannedStops>()
+        .add_event::<FloorSelectedEvent>()
+        .register_type::<PlannedStops>();
+    }
+}
+
+/// The player has picked a floor for the lift to go to
+#[derive(Event, Debug, Clone, Copy)]
+pub struct FloorSelectedEvent {
+    pub floor_num: i32,
+}
+
+/// Floors the player has asked the lift to go to, in order. Each is removed once the lift
+/// stops there
+#[derive(Resource, Debug, Default, Reflect)]
+pub struct PlannedStops(pub VecDeque<i32>);
+
+impl PlannedStops {
+    pub fn next(&self) -> Option<i32> {
+        self.0.front().copied()
+    }
+}
+
+/// The floor in the shaft at [world_position], if any
+pub fn shaft_floor_at(
+    world_position: Vec2,
+    floors: &Floors,
+    shaft_centre_x: &ShaftCentreX,
+    lift_limits: &LiftLimits,
+) -> Option<i32> {
+    if (world_position.x - shaft_centre_x.0).abs() > TILE_HALF_WIDTH {
+        return None;
+    }
+    floors
+        .closest_floor(world_position.y)
+        .filter(|(_, floor_y)| (world_position.y - floor_y).abs() <= TILE_HALF_WIDTH)
+        .filter(|(_, floor_y)| (lift_limits.min..=lift_limits.max).contains(floor_y))
+        .map(|(floor_num, _)| floor_num)
+}
+
+fn floor_tap_system(
+    touches: Res<Touches>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    dial_touch: Res<DialTouch>,
+    floors: Res<Floors>,
+    shaft_centre_x: Res<ShaftCentreX>,
+    lift_limits: Res<LiftLimits>,
+    mut floor_selected_events: EventWriter<FloorSelectedEvent>,
+) {
+    let (camera, camera_transform) = camera_query.single();
+    for touch in touches.iter_just_released() {
+        let moved = touch.start_position().distance(touch.position());
+        if moved > TAP_MAX_MOVEMENT || dial_touch.0 == Some(touch.id()) {
+            continue;
+        }
+        if let Some(floor_num) = camera
+            .viewport_to_world(camera_transform, touch.position())
+            .map(|ray| ray.origin.truncate())
+            .and_then(|world_position| {
+                shaft_floor_at(world_position, &floors, &shaft_centre_x, &lift_limits)
+            })
+        {
+            floor_selected_events.send(FloorSelectedEvent { floor_num });
+        }
+    }
+}
+
+fn floor_click_system(
+    inputs: Query<&ActionState<InputAction>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    vestibule_query: Query<(&GlobalTransform, &FloorNum), With<HumanStore>>,
+    selection_enabled: Res<SelectionEnabled>,
+    minimap_hovered: Res<MinimapHovered>,
+    mut contexts: EguiContexts,
+    floors: Res<Floors>,
+    shaft_centre_x: Res<ShaftCentreX>,
+    lift_limits: Res<LiftLimits>,
+    mut floor_selected_events: EventWriter<FloorSelectedEvent>,
+) {
+    let Ok(inputs) = inputs.get_single() else {
+        return;
+    };
+    // Clicks on the dial, minimap or a ui window aren't for us
+    if !inputs.just_pressed(&InputAction::MouseLClick)
+        || selection_enabled.0
+        || minimap_hovered.0
+        || contexts.ctx_mut().is_pointer_over_area()
+    {
+        return;
+    }
+    let (camera, camera_transform) = camera_query.single();
+    let Some(world_position) = window_query
+        .single()
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
+        .map(|ray| ray.origin.truncate())
+    else {
+        return;
+    };
+
+    let vestibule_floor = vestibule_query
+        .iter()
+        .find(|(transform, _)| {
+            let offset = world_position - transform.translation().truncate();
+            offset.x.abs() <= TILE_HALF_WIDTH && offset.y.abs() <= TILE_HALF_WIDTH
+        })
+        .map(|(_, floor_num)| floor_num.0);
+    if let Some(floor_num) = vestibule_floor
+        .or_else(|| shaft_floor_at(world_position, &floors, &shaft_centre_x, &lift_limits))
+    {
+        floor_selected_events.send(FloorSelectedEvent { floor_num });
+    }
+}
+
+fn plan_stops_system(
+    mut floor_selected_events: EventReader<FloorSelectedEvent>,
+    mut planned_stops: ResMut<PlannedStops>,
+) {
+    for event in floor_selected_events.read() {
+        if !planned_stops.0.contains(&event.floor_num) {
+            info!("Planned stop at floor {}", event.floor_num);
+            planned_stops.0.push_back(event.floor_num);
+        }
+    }
+}
+
+fn complete_stops_system(
+    proximity_query: Query<&FloorProximity>,
+    mut planned_stops: ResMut<PlannedStops>,
+) {
+    for proximity in proximity_query.iter() {
+        if let Some(floor_num) = proximity.stopped_at() {
+            if planned_stops.0.contains(&floor_num) {
+                planned_stops.0.retain(|stop| *stop != floor_num);
+            }
+        }
+    }
+}
+
+/// In assisted driving, fly the lift to the next planned stop instead of following the dial
+pub fn assisted_drive_system(
+    lift_query: Query<(&Transform, &LinearVelocity), With<Lift>>,
+    planned_stops: Res<PlannedStops>,
+    floors: Res<Floors>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+    mut target_velocity: ResMut<TargetVelocity>,
+) {
+    if !settings.assisted_driving {
+        return;
+    }
+    let Some((_, target_y)) = planned_stops.next().and_then(|next_stop| {
+        floors
+            .floor_y_positions
+            .iter()
+            .find(|(floor_num, _)| *floor_num == next_stop)
+    }) else {
+        return;
+    };
+    for (transform, velocity) in lift_query.iter() {
+        let (min_velocity, max_velocity) = velocity.bounds();
+        let limits = MotionLimits {
+            max_speed: f32::min(-min_velocity, max_velocity),
+            max_accel: velocity.max_accel(),
+        };
+        target_velocity.0 = motion_planning::velocity_towards(
+            transform.translation.y,
+            *target_y,
+            limits,
+            time.delta_seconds(),
+        );
+    }
+}
+
+fn planned_stops_gizmo_system(
+    planned_stops: Res<PlannedStops>,
+    floors: Res<Floors>,
+    shaft_centre_x: Res<ShaftCentreX>,
+    mut gizmos: Gizmos,
+) {
+    for (i, stop) in planned_stops.0.iter().enumerate() {
+        if let Some((_, floor_y)) = floors
+            .floor_y_positions
+            .iter()
+            .find(|(floor_num, _)| floor_num == stop)
+        {
+            let colour = if i == 0 { Color::GREEN } else { Color::YELLOW };
+            gizmos.rect_2d(
+                Vec2::new(shaft_centre_x.0, *floor_y),
+                0.0,
+                Vec2::splat(TILE_HALF_WIDTH * 2.0),
+                colour,
+            );
+        }
+    }
+}
+
+fn planned_stops_ui_system(mut contexts: EguiContexts, mut planned_stops: ResMut<PlannedStops>) {
+    if planned_stops.0.is_empty() {
+        return;
+    }
+    egui::Window::new("Planned Stops").show(contexts.ctx_mut(), |ui| {
+        let mut remove = None;
+        for (i, stop) in planned_stops.0.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(format!("{}. {}", i + 1, floor_num_pretty_str(*stop)));
+                if ui.small_button("x").clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+        if let Some(i) = remove {
+            planned_stops.0.remove(i);
+        }
+        if ui.button("Clear").clicked() {
+            planned_stops.0.clear();
+        }
+    });
+}
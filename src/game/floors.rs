@@ -1,9 +1,13 @@
-use crate::game::game::MAP_Z;
+use crate::camera::CameraBounds;
+use crate::game::game::{DayScoped, MAP_Z};
 use crate::game::game_clock::GameTime;
 use crate::game::human_store;
 use crate::game::human_store::{Human, HumanStore, HumanStoreBundle, PositionIndex};
-use crate::game::spawn_simulation::{prefabs, FloorSpawnManager, RawFloorConfig};
+use crate::game::seed::{DaySeed, GameRng};
+use crate::game::spawn_simulation::{EventSchedule, FloorSpawnManager};
+use crate::game::telemetry::{SessionEvent, SessionLog};
 use crate::game::ui::GameCentralInfo;
+use crate::game::world_gen::Level;
 use crate::loading::TextureAssets;
 use bevy::ecs::system::EntityCommands;
 use bevy::hierarchy::BuildChildren;
@@ -17,11 +21,10 @@ use bevy_ecs_tilemap::prelude::{
     TileTextureIndex, TilemapArrayTexture,
 };
 use bevy_ecs_tilemap::TilemapBundle;
-use rand::rngs::ThreadRng;
-use rand::seq::SliceRandom;
-use rand::{thread_rng, Rng};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::fmt::Formatter;
 
 #[derive(Resource, Debug, Default, Reflect)]
@@ -44,6 +47,31 @@ impl Floors {
             .min_by(|(_, _, a), (_, _, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
             .map(|(floor, floor_y, _)| (floor, *floor_y))
     }
+
+    /// Distance between neighbouring floors
+    pub fn floor_height(&self) -> Option<f32> {
+        match self.floor_y_positions.as_slice() {
+            [(_, first), (_, second), ..] => Some((second - first).abs()),
+            _ => None,
+        }
+    }
+
+    /// Identifies the building's layout, so runs can be compared only against others in the
+    /// same building. FNV-1a, as it has to be stable across builds
+    pub fn fingerprint(&self) -> u64 {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for (floor_num, floor_y) in self.floor_y_positions.iter() {
+            for byte in floor_num
+                .to_le_bytes()
+                .into_iter()
+                .chain(floor_y.to_bits().to_le_bytes())
+            {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        }
+        hash
+    }
 }
 
 /// Marker for the tilemap floor segment of the vestibule (i.e. entrance to lift)
@@ -194,8 +222,10 @@ pub fn build_floor_map(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     array_texture_loader: Res<ArrayTextureLoader>,
+    day_seed: Res<DaySeed>,
+    level: Res<Level>,
 ) {
-    println!("Building floor map");
+    info!("Building floor map");
     let texture: Handle<Image> = asset_server.load("textures/floor_tile.spritesheet.png");
     let tilemap_entity = commands.spawn_empty().id();
     let num_regular_tiles_per_row = 10;
@@ -203,7 +233,7 @@ pub fn build_floor_map(
     let row_width = num_regular_tiles_per_row + 4;
     let shaft_x = num_regular_tiles_per_row + 2;
     // There is a base floor, and a roof
-    let num_regular_floors = 10;
+    let num_regular_floors = level.floor_count() as u32;
     let num_rows = num_regular_floors + 2;
     let map_size = TilemapSize {
         x: row_width as u32,
@@ -219,14 +249,16 @@ pub fn build_floor_map(
     let grid_size = tile_size.into();
     let map_type = TilemapType::default();
 
-    let mut rng = thread_rng();
+    info!("Day seed: {}", day_seed.0);
+    let mut rng = StdRng::seed_from_u64(day_seed.0);
 
     // Initially populated with raw positions, then will be mapped with the tilemap transform after
     let mut floor_latch_y_positions = Vec::new();
     let mut vestibule_locations = Vec::new();
 
     let mut child_tiles = Vec::new();
-    let mut floor_configs = HashMap::new();
+    // Ordered, so the floors always draw from the rng in the same order
+    let mut floor_configs = BTreeMap::new();
     for floor_num in 0..num_rows {
         let row = if floor_num == 0 {
             make_bottom_row(row_width)
@@ -256,9 +288,11 @@ pub fn build_floor_map(
             tile_storage.set(&tile_pos, tile_entity);
             child_tiles.push(tile_entity);
         }
-        // TODO: Think about how to pick the config here
-        let floor_config = prefabs::generate_config_of_floor_num(floor_num as i32);
-        floor_configs.insert(FloorNum(floor_num as i32), floor_config);
+        // Only floors with a vestibule can have people come and go
+        if floor_num != 0 && floor_num != num_rows - 1 {
+            let floor_config = level.spawn_config(floor_num as usize - 1, floor_num as i32);
+            floor_configs.insert(FloorNum(floor_num as i32), floor_config);
+        }
     }
     commands.entity(tilemap_entity).push_children(&child_tiles);
 
@@ -277,7 +311,8 @@ pub fn build_floor_map(
             transform: tilemap_transform,
             ..Default::default()
         })
-        .insert(Name::new("TileMap"));
+        .insert(Name::new("TileMap"))
+        .insert(DayScoped);
     array_texture_loader.add(TilemapArrayTexture {
         texture: TilemapTexture::Single(texture),
         tile_size,
@@ -293,6 +328,7 @@ pub fn build_floor_map(
                 floor_num,
                 pos.extend(MAP_Z + 1.0),
             ))
+            .insert(DayScoped)
             .with_children(|parent| {
                 parent
                     .spawn(Text2dBundle {
@@ -318,7 +354,7 @@ pub fn build_floor_map(
         .map(|y| y + tilemap_transform.translation.y)
         .collect();
     let floor_latch_y_positions = FloorLatchYPositions(floor_latch_y_positions_raw.clone());
-    println!("Inserting {:?}", floor_latch_y_positions);
+    debug!("Inserting {:?}", floor_latch_y_positions);
     commands.insert_resource(floor_latch_y_positions);
 
     let floors: Vec<(i32, f32)> = floor_latch_y_positions_raw
@@ -329,12 +365,17 @@ pub fn build_floor_map(
     let floors = Floors {
         floor_y_positions: floors,
     };
-    println!("Inserting {:?}", floors);
+    debug!("Inserting {:?}", floors);
     commands.insert_resource(floors);
 
+    // Tile positions are their centres, so the map starts half a tile before the first
+    let map_min = tilemap_transform.translation.truncate() - tile_size_vec2 / 2.0;
+    let map_max = map_min + tile_size_vec2 * Vec2::new(map_size.x as f32, map_size.y as f32);
+    commands.insert_resource(CameraBounds(Rect::from_corners(map_min, map_max)));
+
     let shaft_centre_x = (shaft_x as f32 * tile_size.x) + tilemap_transform.translation.x;
     let shaft_centre_x = ShaftCentreX(shaft_centre_x);
-    println!("Inserting {:?}", shaft_centre_x);
+    debug!("Inserting {:?}", shaft_centre_x);
     commands.insert_resource(shaft_centre_x);
 
     let lift_limits = {
@@ -355,12 +396,16 @@ pub fn build_floor_map(
             max: top_floor.y,
         }
     };
-    println!("Inserting {:?}", lift_limits);
+    debug!("Inserting {:?}", lift_limits);
     commands.insert_resource(lift_limits);
 
-    let floor_spawn_manager = FloorSpawnManager::new(floor_configs);
-    println!("Inserting Floor Spawn Manager: {:?}", floor_spawn_manager);
+    let floor_nums: Vec<FloorNum> = floor_configs.keys().cloned().collect();
+    let schedule = EventSchedule::generate(&floor_nums, &mut rng);
+    let floor_spawn_manager =
+        FloorSpawnManager::new(floor_configs, &mut rng).with_schedule(schedule);
+    debug!("Inserting Floor Spawn Manager: {:?}", floor_spawn_manager);
     commands.insert_resource(floor_spawn_manager);
+    commands.insert_resource(GameRng(rng));
 }
 
 #[derive(Resource, Debug, Default, Reflect)]
@@ -394,21 +439,35 @@ pub struct SpawnHumansEvent {
     destination_floor: FloorNum,
 }
 
+impl SpawnHumansEvent {
+    pub fn new(spawn_at_floor: FloorNum, destination_floor: FloorNum) -> Self {
+        Self {
+            spawn_at_floor,
+            destination_floor,
+        }
+    }
+}
+
 pub fn floor_spawn_process_system(
     mut floor_spawn_manager: ResMut<FloorSpawnManager>,
     mut spawn_humans_event_writer: EventWriter<SpawnHumansEvent>,
     time: Res<Time>,
     game_central_info: Res<GameCentralInfo>,
+    mut session_log: ResMut<SessionLog>,
+    mut rng: ResMut<GameRng>,
 ) {
-    let mut rand = thread_rng();
-    // TODO: Handle rng properly
-    let spawns = floor_spawn_manager.tick(&game_central_info.time, time.delta(), &mut rand);
+    let spawns = floor_spawn_manager.tick(&game_central_info.time, time.delta(), &mut rng.0);
     for (spawn_at_floor, destination_floor) in spawns {
+        session_log.push(SessionEvent::spawn(
+            game_central_info.time.elapsed_secs(),
+            spawn_at_floor.0,
+            destination_floor.0,
+        ));
         let event = SpawnHumansEvent {
             spawn_at_floor,
             destination_floor,
         };
-        println!("Sending: {:?}", event);
+        debug!("Sending: {:?}", event);
         spawn_humans_event_writer.send(event);
     }
 }
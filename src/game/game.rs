@@ -1,4 +1,5 @@
-use crate::camera::{CameraTrack, RENDER_LAYER_MAIN};
+use crate::camera::{CameraBounds, CameraTrack, RENDER_LAYER_MAIN};
+use crate::core::TransformTween;
 use crate::game::floors::{
     floor_spawn_process_system, human_store_spawn_humans_system, FloorLatchYPositions, FloorNum,
     FloorRegular, FloorShaft, FloorVestibule, Floors, LiftLimits, PersonSpawnTimer, ShaftCentreX,
@@ -9,30 +10,31 @@ use crate::game::human_store::{
     FloorDesire, HowMany, Human, HumanStore, PositionIndex, Unavailable,
 };
 use crate::game::lift::LiftHumanStore;
+use crate::game::seed::{DaySeed, GameRng};
+use crate::game::spawn_simulation::FloorSpawnManager;
 use crate::game::speed_selector::TargetVelocity;
-use crate::game::world_gen::Floor;
-use crate::game::{floors, lift};
-use crate::history_store::HistoryStore;
-use crate::input_action::InputAction;
+use crate::game::telemetry::{SessionEvent, SessionLog};
+use crate::game::ui::GameCentralInfo;
+use crate::game::world_gen::{Floor, Level};
+use crate::game::{floor_select, floors, lift};
+use crate::history_store::{HistoryStore, LttbMethod, MinMaxMeanMethod, PersistenceMethod};
+use crate::input_action::{InputAction, Keybindings};
 use crate::loading::TextureAssets;
 use crate::{camera, GameState};
 use bevy::ecs::system::EntityCommands;
 use bevy::prelude::*;
 use bevy::render::view::RenderLayers;
 use bevy_ecs_tilemap::prelude::*;
-use bevy_inspector_egui::inspector_options::Target;
 use derive_new::new;
 use leafwing_input_manager::prelude::*;
-use leafwing_input_manager::user_input::InputKind::Mouse;
 use rand::{thread_rng, Rng};
 use std::cmp::Ordering;
-use std::f32::consts::TAU;
 use std::time::Duration;
 
 pub struct GamePlugin;
 
 pub const MAP_Z: f32 = 0.5;
-const LIFT_Z: f32 = 210.0;
+pub const LIFT_Z: f32 = 210.0;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
@@ -40,37 +42,67 @@ impl Plugin for GamePlugin {
             OnEnter(GameState::PlayingDay),
             (floors::build_floor_map, setup_game, setup_background).chain(),
         )
+        .add_systems(OnExit(GameState::PlayingDay), despawn_day_scoped)
         .add_systems(
             Update,
+            human_store::floor_desire_system.run_if(in_state(GameState::PlayingDay)),
+        )
+        .configure_sets(
+            FixedUpdate,
             (
-                lift_gizmo_system,
-                debug_lift_mode_text,
-                floor_proximity_system,
-                proximity_timer_display_system,
-                floor_proximity_effect_system.after(floor_proximity_system),
-                floor_spawn_process_system,
-                human_store_spawn_humans_system,
-                human_store::floor_desire_system,
-                human_store::human_marker_component_system,
-                lift::LiftHumanStore::update_system,
+                DaySimulationSet::Clock,
+                DaySimulationSet::Input,
+                DaySimulationSet::Movement,
+                DaySimulationSet::World,
             )
+                .chain()
                 .run_if(in_state(GameState::PlayingDay)),
         )
         .add_systems(
             FixedUpdate,
-            ((lift_latch_system, move_lift_system).chain(),)
-                .run_if(in_state(GameState::PlayingDay)),
+            (
+                (lift_latch_system, floor_select::assisted_drive_system)
+                    .chain()
+                    .in_set(DaySimulationSet::Input),
+                (move_lift_system, lift::LiftHumanStore::ride_comfort_system)
+                    .chain()
+                    .in_set(DaySimulationSet::Movement),
+                (
+                    floor_proximity_system,
+                    floor_proximity_effect_system,
+                    floor_spawn_process_system,
+                    human_store_spawn_humans_system,
+                    human_store::human_marker_component_system.after(TransformTween::update_system),
+                    human_store::waiting_patience_system,
+                    lift::LiftHumanStore::update_system,
+                )
+                    .chain()
+                    .in_set(DaySimulationSet::World),
+            ),
         )
-        .insert_resource(VelocityLog(HistoryStore::new(512, 1024, 60)))
-        .insert_resource(ObservedVelocityLog(HistoryStore::new(512, 1024, 60)))
-        .insert_resource(AccelerationLog(HistoryStore::new(512, 1024, 60)))
+        .init_day_resource::<VelocityLog>()
+        .init_day_resource::<ObservedVelocityLog>()
+        .init_day_resource::<AccelerationLog>()
+        .init_day_resource::<JerkLog>()
+        // Inserted by [floors::build_floor_map]
+        .remove_on_day_exit::<FloorLatchYPositions>()
+        .remove_on_day_exit::<Floors>()
+        .remove_on_day_exit::<ShaftCentreX>()
+        .remove_on_day_exit::<LiftLimits>()
+        .remove_on_day_exit::<FloorSpawnManager>()
+        .remove_on_day_exit::<GameRng>()
+        .remove_on_day_exit::<CameraBounds>()
+        .init_resource::<DaySeed>()
+        .init_resource::<Level>()
         .insert_resource(PersonSpawnTimer(Timer::from_seconds(
             5.0,
             TimerMode::Repeating,
         )))
         .add_event::<SpawnHumansEvent>()
+        .register_type::<DayScoped>()
         .register_type::<LiftMode>()
         .register_type::<LinearVelocity>()
+        .register_type::<LiftMotion>()
         .register_type::<FloorProximity>()
         .register_type::<FloorProximitySensor>()
         .register_type::<FloorShaft>()
@@ -95,24 +127,117 @@ pub struct VelocityLog(pub HistoryStore<(f32, f32)>);
 pub struct ObservedVelocityLog(pub HistoryStore<(f32, f32)>);
 #[derive(Resource, Debug)]
 pub struct AccelerationLog(pub HistoryStore<(f32, f32)>);
+#[derive(Resource, Debug)]
+pub struct JerkLog(pub HistoryStore<(f32, f32)>);
+
+impl Default for VelocityLog {
+    fn default() -> Self {
+        Self(HistoryStore::with_persistence(
+            512,
+            1024,
+            PersistenceMethod::Lttb(LttbMethod::new(60)),
+        ))
+    }
+}
+impl Default for ObservedVelocityLog {
+    fn default() -> Self {
+        Self(HistoryStore::with_persistence(
+            512,
+            1024,
+            PersistenceMethod::Lttb(LttbMethod::new(60)),
+        ))
+    }
+}
+impl Default for AccelerationLog {
+    fn default() -> Self {
+        // Three samples per bucket, so three times the bucket size for the same density
+        Self(HistoryStore::with_persistence(
+            512,
+            1024,
+            PersistenceMethod::MinMaxMean(MinMaxMeanMethod::new(180)),
+        ))
+    }
+}
+impl Default for JerkLog {
+    fn default() -> Self {
+        Self(HistoryStore::with_persistence(
+            512,
+            1024,
+            PersistenceMethod::MinMaxMean(MinMaxMeanMethod::new(180)),
+        ))
+    }
+}
+
+/// Ordering of the day's simulation within [FixedUpdate]. Everything which affects how a day
+/// plays out runs in here at a fixed rate and in a fixed order, so given the same [DaySeed]
+/// and the same inputs a day always plays out the same (see [crate::game::replay])
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DaySimulationSet {
+    /// Advancing the game clock
+    Clock,
+    /// Deciding the lift's [TargetVelocity]
+    Input,
+    Movement,
+    /// Everything reacting to where the lift is and what time it is: spawns, pickups and
+    /// deliveries
+    World,
+}
+
+/// Marker for entities which only live for the duration of a day, despawned on leaving
+/// [GameState::PlayingDay]
+#[derive(Component, Debug, Default, Reflect)]
+pub struct DayScoped;
+
+fn despawn_day_scoped(mut commands: Commands, query: Query<Entity, With<DayScoped>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// The resource equivalent of [DayScoped]
+pub trait DayScopedResourceExt {
+    /// Insert a fresh [R] on entering [GameState::PlayingDay] and remove it on leaving
+    fn init_day_resource<R: Resource + FromWorld>(&mut self) -> &mut Self;
+    /// For resources inserted by the day's setup systems, which only need removing
+    fn remove_on_day_exit<R: Resource>(&mut self) -> &mut Self;
+}
+
+impl DayScopedResourceExt for App {
+    fn init_day_resource<R: Resource + FromWorld>(&mut self) -> &mut Self {
+        self.add_systems(OnEnter(GameState::PlayingDay), init_resource::<R>)
+            .remove_on_day_exit::<R>()
+    }
+
+    fn remove_on_day_exit<R: Resource>(&mut self) -> &mut Self {
+        self.add_systems(OnExit(GameState::PlayingDay), remove_resource::<R>)
+    }
+}
+
+fn init_resource<R: Resource + FromWorld>(mut commands: Commands) {
+    commands.init_resource::<R>();
+}
+
+fn remove_resource<R: Resource>(mut commands: Commands) {
+    commands.remove_resource::<R>();
+}
 
 fn setup_background(mut commands: Commands, assets: Res<TextureAssets>) {
-    commands.spawn(SpriteBundle {
-        texture: assets.city_background_1.clone(),
-        transform: Transform::from_translation(Vec3::new(0.0, 0.0, 0.0)),
-        ..default()
-    });
+    commands
+        .spawn(SpriteBundle {
+            texture: assets.city_background_1.clone(),
+            transform: Transform::from_translation(Vec3::new(0.0, 0.0, 0.0)),
+            ..default()
+        })
+        .insert(DayScoped);
 }
 
-fn setup_game(mut commands: Commands, asset_server: Res<AssetServer>) {
-    println!("Setting up game");
-    let mut input_map = InputMap::default();
-    input_map.insert(InputAction::Up, KeyCode::KeyW);
-    input_map.insert(InputAction::Down, KeyCode::KeyS);
-    input_map.insert(InputAction::MouseMove, SingleAxis::mouse_motion_y());
-    input_map.insert(InputAction::MouseLClick, Mouse(MouseButton::Left));
-    input_map.insert(InputAction::ZoomIn, KeyCode::KeyQ);
-    input_map.insert(InputAction::ZoomOut, KeyCode::KeyE);
+fn setup_game(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    keybindings: Res<Keybindings>,
+) {
+    info!("Setting up game");
+    let input_map = keybindings.input_map();
     let texture = asset_server.load("textures/lift.png");
     commands
         .spawn(SpriteBundle {
@@ -122,10 +247,15 @@ fn setup_game(mut commands: Commands, asset_server: Res<AssetServer>) {
         })
         .insert(Name::new("Lift"))
         .insert(Lift)
+        .insert(DayScoped)
         .insert(LiftMode::Free)
         .insert(LinearVelocity::new((-100.0, 100.0), 100.0))
         .insert(ObservedVelocity(0.0))
-        .insert(CameraTrack { y_threshold: 50.0 })
+        .insert(LiftMotion::default())
+        .insert(CameraTrack {
+            offset: Vec2::new(-200.0, 0.0),
+            threshold: Vec2::new(150.0, 50.0),
+        })
         .insert(InputManagerBundle::<InputAction> {
             input_map,
             ..Default::default()
@@ -136,13 +266,6 @@ fn setup_game(mut commands: Commands, asset_server: Res<AssetServer>) {
             max_velocity: 2.5,
             floor_timer_duration: Duration::from_secs(2),
         });
-
-    commands
-        .spawn(Text2dBundle {
-            text: Text::from_section("", TextStyle::default()),
-            ..default()
-        })
-        .insert(LiftModeDebugText);
 }
 
 fn lift_latch_system(
@@ -154,16 +277,27 @@ fn lift_latch_system(
 }
 
 fn move_lift_system(
-    mut lift_query: Query<(&mut Transform, &mut LinearVelocity, &mut ObservedVelocity), With<Lift>>,
+    mut lift_query: Query<
+        (
+            &mut Transform,
+            &mut LinearVelocity,
+            &mut ObservedVelocity,
+            &mut LiftMotion,
+        ),
+        With<Lift>,
+    >,
     time: Res<Time>,
     shaft_centre_x: Res<ShaftCentreX>,
     lift_limits: Res<LiftLimits>,
     target_velocity: Res<TargetVelocity>,
+    game_central_info: Res<GameCentralInfo>,
     mut velocity_log: ResMut<VelocityLog>,
     mut observed_velocity_log: ResMut<ObservedVelocityLog>,
     mut acceleration_log: ResMut<AccelerationLog>,
+    mut jerk_log: ResMut<JerkLog>,
 ) {
-    let (mut lift_transform, mut actual_velocity, mut observed_velocity) = lift_query.single_mut();
+    let (mut lift_transform, mut actual_velocity, mut observed_velocity, mut lift_motion) =
+        lift_query.single_mut();
 
     let accel_this_tick = actual_velocity.update(target_velocity.0, time.delta());
 
@@ -172,39 +306,17 @@ fn move_lift_system(
         lift_limits.min,
         lift_limits.max,
     );
-    let dy = (new_y - lift_transform.translation.y).abs();
-    observed_velocity.0 = dy / time.delta_seconds();
+    let dy = new_y - lift_transform.translation.y;
+    observed_velocity.0 = dy.abs() / time.delta_seconds();
+    lift_motion.update(dy / time.delta_seconds(), time.delta_seconds());
     lift_transform.translation.y = new_y;
     lift_transform.translation.x = shaft_centre_x.0;
-    velocity_log
-        .0
-        .push((time.elapsed_seconds(), actual_velocity.velocity));
-    acceleration_log
-        .0
-        .push((time.elapsed_seconds(), accel_this_tick));
-    observed_velocity_log
-        .0
-        .push((time.elapsed_seconds(), observed_velocity.0));
-}
-
-fn lift_gizmo_system(
-    floor_latch_ypositions: Res<FloorLatchYPositions>,
-    shaft_centre_x: Res<ShaftCentreX>,
-    lift_query: Query<&Transform, With<Lift>>,
-    mut gizmos: Gizmos,
-) {
-    let shaft_centre_x = shaft_centre_x.0;
-    for y in floor_latch_ypositions.0.iter() {
-        gizmos.line_2d(
-            Vec2::new(shaft_centre_x - 10.0, *y),
-            Vec2::new(shaft_centre_x + 10.0, *y),
-            Color::BLUE,
-        );
-    }
-
-    for lift in lift_query.iter() {
-        gizmos.circle_2d(lift.translation.truncate(), 10.0, Color::RED);
-    }
+    // On the day's clock, so the histories line up with the session's events once exported
+    let now = game_central_info.time.elapsed_secs();
+    velocity_log.0.push((now, actual_velocity.velocity));
+    acceleration_log.0.push((now, accel_this_tick));
+    observed_velocity_log.0.push((now, observed_velocity.0));
+    jerk_log.0.push((now, lift_motion.jerk));
 }
 
 /// Marker component for the lift
@@ -212,42 +324,13 @@ fn lift_gizmo_system(
 pub struct Lift;
 
 #[derive(Component, Debug, Reflect)]
-enum LiftMode {
+pub(super) enum LiftMode {
     Free,
     Opening,
     Open,
     Closing,
 }
 
-impl LiftMode {
-    fn as_str(&self) -> &str {
-        match self {
-            Self::Free => "free",
-            Self::Opening => "opening",
-            Self::Open => "open",
-            Self::Closing => "closing",
-        }
-    }
-}
-
-#[derive(Component, Debug, Reflect)]
-struct LiftModeDebugText;
-
-// TODO: Consider splitting in two for transform and text content changes
-fn debug_lift_mode_text(
-    lift_query: Query<(&GlobalTransform, &LiftMode), With<Lift>>,
-    mut text_query: Query<(&mut Transform, &mut Text), (Without<Lift>, With<LiftModeDebugText>)>,
-) {
-    for (lift_transform, lift_mode) in lift_query.iter() {
-        for (mut text_transform, mut text) in text_query.iter_mut() {
-            text_transform.translation = lift_transform.translation();
-            text_transform.translation.y += 10.0;
-            text_transform.translation.z = LIFT_Z + 1.0;
-            text.sections[0].value = lift_mode.as_str().to_string();
-        }
-    }
-}
-
 #[derive(Component, Debug, Default, Reflect)]
 pub struct LinearVelocity {
     pub velocity: f32,
@@ -258,6 +341,48 @@ pub struct LinearVelocity {
 #[derive(Component, Debug, Default, Reflect)]
 pub struct ObservedVelocity(f32);
 
+/// Over how long the lift's acceleration is smoothed into what a passenger actually feels, as
+/// the raw acceleration jumps straight to its limit
+const ACCELERATION_SMOOTHING_SECONDS: f32 = 0.2;
+
+/// Smoothed acceleration of the lift and its rate of change (jerk), used for ride comfort
+#[derive(Component, Debug, Default, Reflect)]
+pub struct LiftMotion {
+    /// How fast the lift was last seen moving, which drops to 0.0 at once if it hits the end of
+    /// the shaft
+    pub velocity: f32,
+    pub acceleration: f32,
+    pub jerk: f32,
+}
+
+impl LiftMotion {
+    fn smoothing(delta_seconds: f32) -> f32 {
+        f32::min(1.0, delta_seconds / ACCELERATION_SMOOTHING_SECONDS)
+    }
+
+    /// Feel the lift move at [velocity], as seen over the last [delta_seconds]
+    pub(super) fn update(&mut self, velocity: f32, delta_seconds: f32) {
+        if delta_seconds <= 0.0 {
+            return;
+        }
+        let raw_acceleration = (velocity - self.velocity) / delta_seconds;
+        let acceleration = crate::helpers::lerp(
+            self.acceleration,
+            raw_acceleration,
+            Self::smoothing(delta_seconds),
+        );
+        self.jerk = (acceleration - self.acceleration) / delta_seconds;
+        self.velocity = velocity;
+        self.acceleration = acceleration;
+    }
+
+    /// Jerk felt in the step where the raw acceleration jumps by [acceleration_step], e.g. from
+    /// rest straight to the lift's maximum
+    pub fn jerk_of_step(acceleration_step: f32, delta_seconds: f32) -> f32 {
+        acceleration_step * Self::smoothing(delta_seconds) / delta_seconds
+    }
+}
+
 impl LinearVelocity {
     fn new(bounds: (f32, f32), max_accel: f32) -> Self {
         Self {
@@ -266,6 +391,14 @@ impl LinearVelocity {
             velocity: 0.0,
         }
     }
+    pub fn bounds(&self) -> (f32, f32) {
+        self.bounds
+    }
+
+    pub fn max_accel(&self) -> f32 {
+        self.max_accel
+    }
+
     /// Update self to match target_x, with a maximum change of max_accel
     /// Emits the true acceleration applied
     fn update(&mut self, target_x: f32, delta: Duration) -> f32 {
@@ -285,15 +418,22 @@ impl LinearVelocity {
 }
 
 #[derive(Clone, Debug, Reflect, Component, new)]
-struct FloorProximity {
+pub struct FloorProximity {
     floor_num: i32,
-    time_in_proximity: Timer,
+    pub(super) time_in_proximity: Timer,
+}
+
+impl FloorProximity {
+    /// The floor the lift has stopped at, once it has been there long enough to open
+    pub fn stopped_at(&self) -> Option<i32> {
+        self.time_in_proximity.finished().then_some(self.floor_num)
+    }
 }
 #[derive(Clone, Debug, Reflect, Component, new)]
-struct FloorProximitySensor {
+pub(super) struct FloorProximitySensor {
     abs_distance_threshold: f32,
     max_velocity: f32,
-    floor_timer_duration: Duration,
+    pub(super) floor_timer_duration: Duration,
 }
 
 fn floor_proximity_system(
@@ -355,15 +495,27 @@ fn floor_proximity_effect_system(
     >,
     mut commands: Commands,
     mut held_humans: ResMut<LiftHumanStore>,
+    mut game_central_info: ResMut<GameCentralInfo>,
+    mut session_log: ResMut<SessionLog>,
 ) {
     for proximity in query.iter() {
         if proximity.time_in_proximity.finished() {
-            println!(
-                "Collecting from and delivering to Floor {}!!",
+            debug!(
+                "Collecting from and delivering to Floor {}",
                 proximity.floor_num
             );
-            let humans_desiring_this_floor = held_humans.take_for_floor(proximity.floor_num);
-            println!("Delivered humans: {:?}", humans_desiring_this_floor);
+            let ride_reports = held_humans.take_for_floor(proximity.floor_num);
+            info!("Delivered humans: {:?}", ride_reports);
+            let time_secs = game_central_info.time.elapsed_secs();
+            for report in ride_reports.iter() {
+                game_central_info.record_delivery(report);
+                session_log.push(SessionEvent::delivery(
+                    time_secs,
+                    proximity.floor_num,
+                    report.comfort,
+                    report.tip(),
+                ));
+            }
 
             // Looping the second query inside here seems like it'd be O(n^2) but in practice
             // there will only ever be one floor proximity at a time, so it's fine.
@@ -376,33 +528,21 @@ fn floor_proximity_effect_system(
                         &mut commands,
                         HowMany::N(capacity),
                     );
-                    println!(
+                    info!(
                         "Picked up {} humies ({:?})",
                         picked_up_floor_desires.len(),
                         picked_up_floor_desires
                     );
-                    held_humans.add(picked_up_floor_desires);
+                    for destination in picked_up_floor_desires.iter() {
+                        session_log.push(SessionEvent::pickup(
+                            time_secs,
+                            proximity.floor_num,
+                            destination.0,
+                        ));
+                    }
+                    held_humans.add(proximity.floor_num, picked_up_floor_desires);
                 }
             }
         }
     }
 }
-
-fn proximity_timer_display_system(
-    query: Query<(Option<&FloorProximity>, &FloorProximitySensor, &Transform)>,
-    mut gizmos: Gizmos,
-) {
-    for (proximity, sensor, transform) in query.iter() {
-        if let Some(proximity) = proximity {
-            let fill_pct = proximity.time_in_proximity.elapsed().as_secs_f32()
-                / sensor.floor_timer_duration.as_secs_f32();
-            gizmos.arc_2d(
-                transform.translation.truncate(),
-                0.0,
-                TAU * fill_pct,
-                15.0,
-                Color::BLUE,
-            );
-        }
-    }
-}
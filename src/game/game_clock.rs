@@ -2,16 +2,24 @@ use bevy::prelude::*;
 use bevy::render::view::need_surface_configuration;
 use bevy::time::Stopwatch;
 use std::fmt::Formatter;
+use std::ops::Range;
+use std::str::FromStr;
 use std::time::Duration;
 
-#[derive(Component, Debug, Reflect)]
+#[derive(Clone, Component, Resource, Debug, Reflect)]
 pub struct GameTimeConfig {
     time_per_day: Duration,
+    /// The time of day when the day starts, the clock runs from here until [end_hour]
+    start_hour: u8,
+    /// Exclusive, i.e. 20 means the day ends as the clock hits 20:00
+    end_hour: u8,
 }
 impl Default for GameTimeConfig {
     fn default() -> Self {
         Self {
             time_per_day: Duration::from_secs(240),
+            start_hour: 0,
+            end_hour: 24,
         }
     }
 }
@@ -22,14 +30,35 @@ pub struct GameTime {
 }
 
 impl GameTimeConfig {
-    pub fn to_csv(&self) -> String {
-        format!("{}\n", self.time_per_day.as_secs())
+    /// [working_hours] must be a non-empty range within 0..=24
+    pub fn new(time_per_day: Duration, working_hours: Range<u8>) -> Self {
+        assert!(working_hours.start < working_hours.end && working_hours.end <= 24);
+        Self {
+            time_per_day,
+            start_hour: working_hours.start,
+            end_hour: working_hours.end,
+        }
+    }
+
+    pub fn time_per_day(&self) -> Duration {
+        self.time_per_day
+    }
+
+    pub fn working_hours(&self) -> Range<u8> {
+        self.start_hour..self.end_hour
+    }
+
+    fn hours_per_day(&self) -> f32 {
+        (self.end_hour - self.start_hour) as f32
     }
 }
 
 impl GameTime {
     pub fn new() -> Self {
-        let config = GameTimeConfig::default();
+        Self::with_config(GameTimeConfig::default())
+    }
+
+    pub fn with_config(config: GameTimeConfig) -> Self {
         Self {
             time: Timer::new(config.time_per_day, TimerMode::Once),
             config,
@@ -42,6 +71,15 @@ impl GameTime {
         return self.time.elapsed() >= self.config.time_per_day;
     }
 
+    /// True only on the tick the day completed
+    pub fn just_finished(&self) -> bool {
+        self.time.just_finished()
+    }
+
+    pub fn elapsed_secs(&self) -> f32 {
+        self.time.elapsed_secs()
+    }
+
     pub fn to_string_secs(&self) -> String {
         self.time.elapsed().as_secs().to_string()
     }
@@ -54,13 +92,23 @@ impl GameTime {
         // Based on configured time per day, convert time elapsed to a time of day
         let fraction_elapsed =
             self.time.elapsed().as_secs_f32() / self.config.time_per_day.as_secs_f32();
-        let hour = (fraction_elapsed * 24.0) as u8;
-        let minute = ((fraction_elapsed * 24.0) % 1.0 * 60.0) as u8;
+        let hours_elapsed = fraction_elapsed * self.config.hours_per_day();
+        let hour = self.config.start_hour + hours_elapsed as u8;
+        let minute = (hours_elapsed % 1.0 * 60.0) as u8;
         TimeOfDay { hour, minute }
     }
 
+    /// Jump the clock to [time], clamped to the working hours
+    pub fn set_time_of_day(&mut self, time: TimeOfDay) {
+        let minutes_per_day = self.config.hours_per_day() * 60.0;
+        let minutes = time.minutes_since_midnight() as f32 - self.config.start_hour as f32 * 60.0;
+        let fraction = minutes.clamp(0.0, minutes_per_day) / minutes_per_day;
+        self.time
+            .set_elapsed(self.config.time_per_day.mul_f32(fraction));
+    }
+
     pub fn to_hrs_f32(&self, duration: &Duration) -> f32 {
-        let secs_per_hr = self.config.time_per_day.as_secs_f32() / 24.0;
+        let secs_per_hr = self.config.time_per_day.as_secs_f32() / self.config.hours_per_day();
         duration.as_secs_f32() / secs_per_hr
     }
 }
@@ -71,6 +119,32 @@ pub struct TimeOfDay {
     pub minute: u8,
 }
 
+impl TimeOfDay {
+    pub fn new(hour: u8, minute: u8) -> Self {
+        Self { hour, minute }
+    }
+
+    pub fn minutes_since_midnight(&self) -> u16 {
+        self.hour as u16 * 60 + self.minute as u16
+    }
+}
+
+/// Parses the same "HH:MM" format it's displayed in
+impl FromStr for TimeOfDay {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Expected a time like 09:30, got {}", s);
+        let (hour, minute) = s.split_once(':').ok_or_else(invalid)?;
+        let hour: u8 = hour.parse().map_err(|_| invalid())?;
+        let minute: u8 = minute.parse().map_err(|_| invalid())?;
+        if hour > 24 || minute > 59 || (hour == 24 && minute > 0) {
+            return Err(invalid());
+        }
+        Ok(Self { hour, minute })
+    }
+}
+
 impl std::fmt::Display for TimeOfDay {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:02}:{:02}", self.hour, self.minute)
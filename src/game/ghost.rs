@@ -0,0 +1,153 @@
+//! Racing yourself: a translucent lift following the trajectory of the best saved [Replay] of
+//! the same seed in the same building.
+
+use crate::camera::RENDER_LAYER_MAIN;
+use crate::game::floors::{self, Floors, ShaftCentreX};
+use crate::game::game::{DayScoped, DayScopedResourceExt, DaySimulationSet, LIFT_Z};
+use crate::game::replay::{Replay, TrajectorySample};
+use crate::game::seed::DaySeed;
+use crate::game::telemetry::SessionLog;
+use crate::settings::Settings;
+use crate::GameState;
+use bevy::prelude::*;
+use bevy::render::view::RenderLayers;
+use bevy_egui::egui::{Align2, Color32, RichText};
+use bevy_egui::{egui, EguiContexts};
+use std::cmp::Ordering;
+
+const GHOST_TINT: Color = Color::rgba(0.5, 0.8, 1.0, 0.4);
+
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::PlayingDay),
+            spawn_ghost_system.after(floors::build_floor_map),
+        )
+        .add_systems(
+            FixedUpdate,
+            move_ghost_system.in_set(DaySimulationSet::Movement),
+        )
+        .add_systems(
+            Update,
+            ghost_delta_ui_system.run_if(in_state(GameState::PlayingDay)),
+        )
+        .remove_on_day_exit::<GhostRun>();
+    }
+}
+
+/// The run being raced, present only if there is one
+#[derive(Resource, Debug)]
+pub struct GhostRun {
+    replay: Replay,
+    tick: usize,
+}
+
+impl GhostRun {
+    /// The ghost stays put at the end of its run if the current day goes on longer
+    fn sample(&self) -> Option<&TrajectorySample> {
+        self.replay
+            .trajectory
+            .get(self.tick)
+            .or(self.replay.trajectory.last())
+    }
+}
+
+#[derive(Component, Debug)]
+struct GhostLift;
+
+/// The saved run of [seed] in [building] with the most deliveries, if any
+pub fn find_best_run(seed: u64, building: u64) -> Option<Replay> {
+    Replay::list_saved_of(seed, building)
+        .iter()
+        .filter_map(|path| Replay::load(path).ok())
+        .filter(|replay| replay.seed == seed && replay.building == building)
+        .filter_map(|replay| replay.final_deliveries().map(|best| (best, replay)))
+        .max_by_key(|(deliveries, _)| *deliveries)
+        .map(|(_, replay)| replay)
+}
+
+fn spawn_ghost_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+    day_seed: Res<DaySeed>,
+    floors: Res<Floors>,
+    shaft_centre_x: Res<ShaftCentreX>,
+) {
+    if !settings.show_ghost {
+        return;
+    }
+    let Some(replay) = find_best_run(day_seed.0, floors.fingerprint()) else {
+        info!("No previous run of seed {} to race", day_seed.0);
+        return;
+    };
+    info!(
+        "Racing ghost with {} deliveries",
+        replay.final_deliveries().unwrap_or_default()
+    );
+    let ghost_run = GhostRun { replay, tick: 0 };
+    let start_y = ghost_run.sample().map_or(0.0, |sample| sample.lift_y);
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: GHOST_TINT,
+                ..default()
+            },
+            texture: asset_server.load("textures/lift.png"),
+            // Just behind the real lift
+            transform: Transform::from_xyz(shaft_centre_x.0, start_y, LIFT_Z - 1.0),
+            ..default()
+        })
+        .insert(Name::new("Ghost Lift"))
+        .insert(GhostLift)
+        .insert(DayScoped)
+        .insert(RenderLayers::layer(RENDER_LAYER_MAIN));
+    commands.insert_resource(ghost_run);
+}
+
+/// Trajectories are sampled before each tick's movement, so the next sample is where the ghost
+/// was after it
+fn move_ghost_system(
+    ghost_run: Option<ResMut<GhostRun>>,
+    mut ghost_query: Query<&mut Transform, With<GhostLift>>,
+) {
+    let Some(mut ghost_run) = ghost_run else {
+        return;
+    };
+    ghost_run.tick += 1;
+    if let Some(sample) = ghost_run.sample().copied() {
+        for mut transform in ghost_query.iter_mut() {
+            transform.translation.y = sample.lift_y;
+        }
+    }
+}
+
+fn ghost_delta_ui_system(
+    mut contexts: EguiContexts,
+    ghost_run: Option<Res<GhostRun>>,
+    session_log: Res<SessionLog>,
+) {
+    let Some(ghost_run) = ghost_run else {
+        return;
+    };
+    let ghost_deliveries = ghost_run.sample().map_or(0, |sample| sample.deliveries);
+    let delta = session_log.deliveries() as i64 - ghost_deliveries as i64;
+    let (text, colour) = match delta.cmp(&0) {
+        Ordering::Greater => (format!("Ahead by {}", delta), Color32::GREEN),
+        Ordering::Less => (format!("Behind by {}", -delta), Color32::RED),
+        Ordering::Equal => ("Level".to_string(), Color32::WHITE),
+    };
+    egui::Window::new("Ghost")
+        .anchor(Align2::RIGHT_CENTER, egui::Vec2::ZERO)
+        .resizable(false)
+        .collapsible(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label(RichText::new(text).color(colour).size(20.0));
+            ui.label(format!(
+                "Best: {} deliveries",
+                ghost_run.replay.final_deliveries().unwrap_or_default()
+            ));
+        });
+}
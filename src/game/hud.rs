@@ -0,0 +1,273 @@
+//! The in-day heads up display, built with Bevy UI: the clock and money, reputation, the lift's
+//! passengers and its speed.
+
+use crate::game::floors::{floor_num_pretty_str, Floors};
+use crate::game::game::{DayScoped, Lift, LinearVelocity};
+use crate::game::lift::{patience_rgb, patience_warning_visible, LiftHumanStore};
+use crate::game::time_scale::TimeScale;
+use crate::game::ui::GameCentralInfo;
+use crate::loading::TextureAssets;
+use crate::settings::Settings;
+use crate::ui_widgets::{self, hud_panel, hud_text, BarOrientation, UiBar, HUD_TEXT_COLOUR};
+use crate::GameState;
+use bevy::prelude::*;
+
+const HUD_MARGIN: Val = Val::Px(8.0);
+const HUD_FONT_SIZE: f32 = 22.0;
+const PASSENGER_FONT_SIZE: f32 = 18.0;
+const PASSENGER_ICON_SIZE: Vec2 = Vec2::new(20.0, 30.0);
+const REPUTATION_BAR_SIZE: Vec2 = Vec2::new(140.0, 10.0);
+const PATIENCE_BAR_SIZE: Vec2 = Vec2::new(40.0, 6.0);
+/// Below this the lift counts as stopped
+const STOPPED_FLOORS_PER_SEC: f32 = 0.05;
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::PlayingDay), setup_hud)
+            .add_systems(
+                Update,
+                (info_system, passengers_system, speed_system)
+                    .run_if(in_state(GameState::PlayingDay)),
+            );
+    }
+}
+
+#[derive(Component, Debug)]
+enum HudText {
+    Day,
+    Clock,
+    Money,
+    Speed,
+}
+
+#[derive(Component, Debug)]
+struct ReputationBar;
+
+/// Passenger slot widgets, by slot index
+#[derive(Component, Debug)]
+struct PassengerIcon(usize);
+#[derive(Component, Debug)]
+struct PassengerDestination(usize);
+#[derive(Component, Debug)]
+struct PassengerPatience(usize);
+
+const LOW_PATIENCE_WARNING_COLOUR: Color = Color::RED;
+
+fn patience_colour(fraction: f32) -> Color {
+    let [r, g, b] = patience_rgb(fraction);
+    Color::rgb(r, g, b)
+}
+
+fn setup_hud(mut commands: Commands, texture_assets: Res<TextureAssets>, settings: Res<Settings>) {
+    // The store itself is only inserted once the day's started
+    let num_slots = LiftHumanStore::default().max_size();
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(Name::new("HUD"))
+        .insert(DayScoped)
+        .with_children(|root| {
+            root.spawn(hud_panel(Style {
+                position_type: PositionType::Absolute,
+                top: HUD_MARGIN,
+                left: HUD_MARGIN,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                ..default()
+            }))
+            .with_children(|panel| {
+                for text in [HudText::Day, HudText::Clock, HudText::Money] {
+                    panel.spawn(hud_text("", HUD_FONT_SIZE)).insert(text);
+                }
+                panel.spawn(hud_text("Reputation", PASSENGER_FONT_SIZE));
+                ui_widgets::spawn_bar(
+                    panel,
+                    REPUTATION_BAR_SIZE,
+                    Color::GOLD,
+                    BarOrientation::Horizontal,
+                    ReputationBar,
+                );
+            });
+
+            root.spawn(hud_panel(Style {
+                position_type: PositionType::Absolute,
+                top: HUD_MARGIN,
+                right: HUD_MARGIN,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                ..default()
+            }))
+            .with_children(|panel| {
+                panel.spawn(hud_text("Passengers", PASSENGER_FONT_SIZE));
+                for i in 0..num_slots {
+                    panel
+                        .spawn(NodeBundle {
+                            style: Style {
+                                align_items: AlignItems::Center,
+                                column_gap: Val::Px(6.0),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|slot| {
+                            slot.spawn(ImageBundle {
+                                style: Style {
+                                    width: Val::Px(PASSENGER_ICON_SIZE.x),
+                                    height: Val::Px(PASSENGER_ICON_SIZE.y),
+                                    ..default()
+                                },
+                                image: UiImage::new(texture_assets.human_icon_off.clone()),
+                                ..default()
+                            })
+                            .insert(PassengerIcon(i));
+                            slot.spawn(hud_text("", PASSENGER_FONT_SIZE))
+                                .insert(PassengerDestination(i));
+                            ui_widgets::spawn_bar(
+                                slot,
+                                PATIENCE_BAR_SIZE,
+                                patience_colour(1.0),
+                                settings.patience_bar_orientation,
+                                PassengerPatience(i),
+                            );
+                        });
+                }
+            });
+
+            root.spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: HUD_MARGIN,
+                    width: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|row| {
+                row.spawn(hud_panel(Style::default()))
+                    .with_children(|panel| {
+                        panel
+                            .spawn(hud_text("", HUD_FONT_SIZE))
+                            .insert(HudText::Speed);
+                    });
+            });
+        });
+}
+
+fn set_text(text: &mut Text, value: String) {
+    if text.sections[0].value != value {
+        text.sections[0].value = value;
+    }
+}
+
+fn info_system(
+    info: Res<GameCentralInfo>,
+    time_scale: Res<TimeScale>,
+    mut text_query: Query<(&mut Text, &HudText)>,
+    mut reputation_query: Query<&mut UiBar, With<ReputationBar>>,
+) {
+    for (mut text, hud_text) in text_query.iter_mut() {
+        let value = match hud_text {
+            HudText::Day => format!("Day {}", info.day()),
+            HudText::Clock => format!(
+                "{} ({})",
+                info.time.to_game_time_of_day(),
+                time_scale.label()
+            ),
+            HudText::Money => format!("${:.2}", info.money()),
+            HudText::Speed => continue,
+        };
+        set_text(&mut text, value);
+    }
+    for mut bar in reputation_query.iter_mut() {
+        if bar.fraction != info.reputation() {
+            bar.fraction = info.reputation();
+        }
+    }
+}
+
+fn passengers_system(
+    lift_human_store: Res<LiftHumanStore>,
+    texture_assets: Res<TextureAssets>,
+    settings: Res<Settings>,
+    time: Res<Time<Real>>,
+    mut icon_query: Query<(&mut UiImage, &PassengerIcon)>,
+    mut destination_query: Query<(&mut Text, &PassengerDestination)>,
+    mut patience_query: Query<(&mut UiBar, &mut Visibility, &PassengerPatience)>,
+) {
+    let passengers: Vec<_> = lift_human_store.passengers().collect();
+    for (mut image, icon) in icon_query.iter_mut() {
+        let texture = match passengers.get(icon.0) {
+            Some(Some(passenger)) => passenger.kind().icon(&texture_assets),
+            _ => &texture_assets.human_icon_off,
+        };
+        if image.texture != *texture {
+            image.texture = texture.clone();
+        }
+    }
+    // Low patience riders flash their destination as a warning
+    for (mut text, destination) in destination_query.iter_mut() {
+        let (value, colour) = match passengers.get(destination.0) {
+            Some(Some(passenger)) => {
+                let warning =
+                    patience_warning_visible(passenger.patience_fraction(), time.elapsed_seconds());
+                (
+                    floor_num_pretty_str(passenger.destination_floor()),
+                    if warning {
+                        LOW_PATIENCE_WARNING_COLOUR
+                    } else {
+                        HUD_TEXT_COLOUR
+                    },
+                )
+            }
+            _ => (String::new(), HUD_TEXT_COLOUR),
+        };
+        if text.sections[0].style.color != colour {
+            text.sections[0].style.color = colour;
+        }
+        set_text(&mut text, value);
+    }
+    for (mut bar, mut visibility, patience) in patience_query.iter_mut() {
+        match passengers.get(patience.0) {
+            Some(Some(passenger)) => {
+                let fraction = passenger.patience_fraction();
+                bar.fraction = fraction;
+                bar.fill_colour = patience_colour(fraction);
+                bar.orientation = settings.patience_bar_orientation;
+                *visibility = Visibility::Inherited;
+            }
+            _ => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+fn speed_system(
+    lift_query: Query<&LinearVelocity, With<Lift>>,
+    floors: Res<Floors>,
+    mut text_query: Query<(&mut Text, &HudText)>,
+) {
+    let (Ok(velocity), Some(floor_height)) = (lift_query.get_single(), floors.floor_height())
+    else {
+        return;
+    };
+    let floors_per_sec = velocity.velocity / floor_height;
+    let value = if floors_per_sec.abs() < STOPPED_FLOORS_PER_SEC {
+        "Stopped".to_string()
+    } else {
+        let direction = if floors_per_sec > 0.0 { "Up" } else { "Down" };
+        format!("{} {:.1} floors/s", direction, floors_per_sec.abs())
+    };
+    for (mut text, hud_text) in text_query.iter_mut() {
+        if let HudText::Speed = hud_text {
+            set_text(&mut text, value.clone());
+        }
+    }
+}
@@ -1,6 +1,10 @@
 use crate::core::{TransformTween, TweenCompleteEvent};
-use crate::game::floors::{FloorNum, FloorVestibule, Person, PersonSpawnTimer};
+use crate::game::floors::{
+    floor_num_pretty_str, FloorNum, FloorVestibule, Person, PersonSpawnTimer,
+};
 use crate::game::game::MAP_Z;
+use crate::game::telemetry::{SessionEvent, SessionLog};
+use crate::game::ui::GameCentralInfo;
 use crate::loading::TextureAssets;
 use bevy::prelude::*;
 use std::time::Duration;
@@ -37,6 +41,13 @@ pub struct FloorDesire {
     pub floor_num: FloorNum,
 }
 
+/// How long a human waits on their floor before giving up on the lift
+pub const WAITING_PATIENCE: Duration = Duration::from_secs(60);
+
+/// Wears down while a human waits for the lift, see [WAITING_PATIENCE]
+#[derive(Clone, Debug, Component, Reflect)]
+pub struct WaitingPatience(pub Timer);
+
 #[derive(Clone, Debug, Component, Reflect)]
 pub struct PositionIndex(usize);
 
@@ -76,7 +87,7 @@ pub fn add_human_to_store(
     desired_floor: FloorNum,
     commands: &mut Commands,
 ) {
-    println!("Adding human to store");
+    debug!("Adding human to store");
     let max_index = human_query
         .iter()
         .filter_map(|(position_index, parent)| {
@@ -109,6 +120,10 @@ pub fn add_human_to_store(
         .insert(FloorDesire {
             floor_num: desired_floor,
         })
+        .insert(WaitingPatience(Timer::new(
+            WAITING_PATIENCE,
+            TimerMode::Once,
+        )))
         .insert(Unavailable)
         .insert(Name::new("Human"))
         .set_parent(parent_entity);
@@ -153,6 +168,72 @@ pub fn remove_humans(
     removed_desired_floors
 }
 
+/// Humans who run out of [WaitingPatience] leave their floor, and those queued further along
+/// close the gap
+pub fn waiting_patience_system(
+    mut human_query: Query<
+        (
+            Entity,
+            &mut WaitingPatience,
+            &FloorDesire,
+            &mut PositionIndex,
+            &mut Transform,
+            &Parent,
+            Has<TransformTween>,
+        ),
+        With<Human>,
+    >,
+    store_query: Query<&FloorNum, With<HumanStore>>,
+    time: Res<Time>,
+    game_central_info: Res<GameCentralInfo>,
+    mut session_log: ResMut<SessionLog>,
+    mut commands: Commands,
+) {
+    let mut gone = vec![];
+    for (entity, mut patience, floor_desire, position_index, _, parent, _) in human_query.iter_mut()
+    {
+        patience.0.tick(time.delta());
+        if !patience.0.finished() {
+            continue;
+        }
+        let Ok(floor_num) = store_query.get(parent.get()) else {
+            continue;
+        };
+        info!(
+            "A human on {} ran out of patience waiting to go to {}",
+            floor_num_pretty_str(floor_num.0),
+            floor_num_pretty_str(floor_desire.floor_num.0)
+        );
+        session_log.push(SessionEvent::abandon_waiting(
+            game_central_info.time.elapsed_secs(),
+            floor_num.0,
+            floor_desire.floor_num.0,
+        ));
+        commands.entity(entity).despawn_recursive();
+        gone.push((parent.get(), position_index.0));
+    }
+    if gone.is_empty() {
+        return;
+    }
+    for (_, patience, _, mut position_index, mut transform, parent, tweening) in
+        human_query.iter_mut()
+    {
+        if patience.0.finished() {
+            continue;
+        }
+        let shift = gone
+            .iter()
+            .filter(|(store, index)| *store == parent.get() && *index < position_index.0)
+            .count();
+        if shift > 0 {
+            position_index.0 -= shift;
+            if !tweening {
+                transform.translation = position_index.to_translation();
+            }
+        }
+    }
+}
+
 //
 fn human_positioning_system(
     mut human_query: Query<
@@ -1,11 +1,12 @@
-use crate::game::{ui, FloorNum};
+use crate::game::game::{DayScopedResourceExt, LiftMotion, LinearVelocity};
+use crate::game::telemetry::{SessionEvent, SessionLog};
+use crate::game::ui::GameCentralInfo;
+use crate::game::{floor_num_pretty_str, FloorNum};
 use crate::loading::TextureAssets;
 use bevy::app::App;
 use bevy::log::error;
 use bevy::prelude::*;
 use bevy::utils::hashbrown::HashMap;
-use bevy_egui::egui::{Align2, Color32, RichText};
-use bevy_egui::{egui, EguiContexts};
 use std::fmt::{Display, Formatter};
 use std::time::Duration;
 
@@ -15,11 +16,18 @@ pub struct LiftHumanStore {
     max_size: usize,
 }
 
-const EGUI_UI_ENABLED: bool = true;
 #[derive(Debug, Reflect, Clone, Copy)]
 pub enum HumanKind {
     Simon,
 }
+impl HumanKind {
+    pub fn icon<'a>(&self, texture_assets: &'a TextureAssets) -> &'a Handle<Image> {
+        match self {
+            HumanKind::Simon => &texture_assets.human_icon_on,
+        }
+    }
+}
+
 impl Display for HumanKind {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -33,67 +41,134 @@ pub struct StoredHuman {
     destination_floor: i32,
     patience_timer: Timer,
     kind: HumanKind,
+    ride: RideComfort,
+}
+
+/// Tip given by a rider for a perfectly comfortable ride
+const MAX_TIP: f32 = 5.0;
+
+/// Riders put up with this long in the lift, plus [RIDE_PATIENCE_PER_FLOOR] for each floor
+/// they're going, so a long trip isn't a lost cause
+const RIDE_PATIENCE_BASE: Duration = Duration::from_secs(15);
+const RIDE_PATIENCE_PER_FLOOR: Duration = Duration::from_secs(2);
+
+/// How long a rider picked up on [from_floor] puts up with the lift on the way to [to_floor]
+pub fn ride_patience(from_floor: i32, to_floor: i32) -> Duration {
+    RIDE_PATIENCE_BASE + RIDE_PATIENCE_PER_FLOOR * from_floor.abs_diff(to_floor)
+}
+
+/// Smoothed acceleration and jerk below which riders don't mind, and at which they're fully
+/// unhappy
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ComfortLimits {
+    acceleration: (f32, f32),
+    jerk: (f32, f32),
+}
+
+impl ComfortLimits {
+    /// For a lift that can accelerate at up to [max_accel], stepped every [delta_seconds].
+    /// Starting and stopping as hard as the lift can is fine, as is the jerk of going straight
+    /// from rest to full acceleration. Being thrown about harder than the lift can push, by
+    /// hitting the end of the shaft, or swinging from full acceleration one way to the other, is
+    /// not
+    pub fn new(max_accel: f32, delta_seconds: f32) -> Self {
+        let step_jerk = LiftMotion::jerk_of_step(max_accel, delta_seconds);
+        Self {
+            acceleration: (max_accel, 2.0 * max_accel),
+            jerk: (1.25 * step_jerk, 2.0 * step_jerk),
+        }
+    }
+}
+
+/// The worst a rider has felt while in the lift, each from 0.0 for not minding to 1.0
+#[derive(Debug, Default, Reflect, Clone)]
+pub struct RideComfort {
+    acceleration_discomfort: f32,
+    jerk_discomfort: f32,
+}
+
+impl RideComfort {
+    fn record(&mut self, motion: &LiftMotion, limits: &ComfortLimits) {
+        fn discomfort(value: f32, (comfortable, worst): (f32, f32)) -> f32 {
+            ((value.abs() - comfortable) / (worst - comfortable)).clamp(0.0, 1.0)
+        }
+        self.acceleration_discomfort = self
+            .acceleration_discomfort
+            .max(discomfort(motion.acceleration, limits.acceleration));
+        self.jerk_discomfort = self
+            .jerk_discomfort
+            .max(discomfort(motion.jerk, limits.jerk));
+    }
+
+    /// From 1.0 for a perfectly smooth ride down to 0.0 for the worst
+    pub fn score(&self) -> f32 {
+        1.0 - 0.5 * self.acceleration_discomfort - 0.5 * self.jerk_discomfort
+    }
+}
+
+/// What a rider thought of their ride, on being delivered
+#[derive(Debug, Clone)]
+pub struct RideReport {
+    pub patience_remaining: Duration,
+    /// [RideReport::patience_remaining] as a fraction of the rider's total patience
+    pub patience_fraction: f32,
+    pub comfort: f32,
 }
+
+impl RideReport {
+    pub fn tip(&self) -> f32 {
+        MAX_TIP * self.comfort
+    }
+}
+impl StoredHuman {
+    pub fn destination_floor(&self) -> i32 {
+        self.destination_floor
+    }
+
+    pub fn kind(&self) -> HumanKind {
+        self.kind
+    }
+
+    /// From 1.0 when picked up down to 0.0 once out of patience
+    pub fn patience_fraction(&self) -> f32 {
+        self.patience_timer.fraction_remaining()
+    }
+}
+
 #[derive(Debug, Reflect, Clone)]
 pub struct StoredHumanSlot(Option<StoredHuman>);
 
-impl StoredHumanSlot {
-    fn ui_component(&self, ui: &mut egui::Ui, texture_ids: &(egui::TextureId, egui::TextureId)) {
-        let text_color = Color32::WHITE;
-        let size = 24.0;
-
-        // TODO: The below is broken because the patience timer is hard
-        /*
-        match &self.0 {
-            Some(human) => {
-                ui.horizontal(|ui| {
-                    ui.vertical(|ui| {
-                        ui.add(egui::Image::new(egui::load::SizedTexture::new(
-                            texture_ids.0,
-                            [20., 30.],
-                        )));
-                        ui.label(
-                            RichText::new(human.destination_floor.to_string())
-                                .color(text_color)
-                                .size(size),
-                        );
-                    });
-                    crate::ui_widgets::fill_bar(
-                        ui,
-                        Color32::BLACK,
-                        Color32::GREEN,
-                        human.patience_timer.fraction_remaining(),
-                    );
-                });
-            }
-            None => {
-                ui.add(egui::Image::new(egui::load::SizedTexture::new(
-                    texture_ids.1,
-                    [20., 30.],
-                )));
-            }
-        }
-        */
-        let (texture, dest_str) = match &self.0 {
-            Some(human) => (texture_ids.0, human.destination_floor.to_string()),
-            None => (texture_ids.1, "".into()),
-        };
-        ui.horizontal(|ui| {
-            ui.add(egui::Image::new(egui::load::SizedTexture::new(
-                texture,
-                [20., 30.],
-            )));
-            ui.label(RichText::new(dest_str).color(text_color).size(size));
-        });
+/// Riders with less than this fraction of their patience left get a flashing warning
+pub const LOW_PATIENCE: f32 = 0.25;
+const LOW_PATIENCE_FLASHES_PER_SEC: f32 = 2.0;
+
+/// Green when patient, through yellow, to red when out of patience
+pub fn patience_rgb(fraction: f32) -> [f32; 3] {
+    let fraction = fraction.clamp(0.0, 1.0);
+    if fraction > 0.5 {
+        [2.0 * (1.0 - fraction), 1.0, 0.0]
+    } else {
+        [1.0, 2.0 * fraction, 0.0]
     }
 }
 
+/// Whether a low patience warning is in the on half of its flash
+pub fn patience_warning_visible(fraction: f32, elapsed_secs: f32) -> bool {
+    fraction < LOW_PATIENCE && (elapsed_secs * LOW_PATIENCE_FLASHES_PER_SEC).fract() < 0.5
+}
+
 pub fn add(app: &mut App) {
     app.register_type::<LiftHumanStore>()
         .register_type::<HumanKind>()
         .register_type::<StoredHuman>()
         .register_type::<StoredHumanSlot>()
-        .insert_resource(LiftHumanStore::create());
+        .init_day_resource::<LiftHumanStore>();
+}
+
+impl Default for LiftHumanStore {
+    fn default() -> Self {
+        Self::create()
+    }
 }
 
 impl LiftHumanStore {
@@ -121,6 +196,7 @@ impl LiftHumanStore {
                     destination_floor: floor,
                     patience_timer: Timer::new(patience, TimerMode::Once),
                     kind: HumanKind::Simon,
+                    ride: RideComfort::default(),
                 });
                 inserted = true;
                 break;
@@ -136,12 +212,13 @@ impl LiftHumanStore {
     pub fn add_single(&mut self, floor: i32, patience: Duration) -> bool {
         self.add_single_(floor, patience, true)
     }
-    /// The length of floors vec must be less than or equal to the number of free slots, this is
-    /// checked but not enforced, surplus humans will simply cease to exist - sorry.
-    pub fn add(&mut self, floors: Vec<FloorNum>) {
+    /// Riders picked up on [from_floor], see [ride_patience]. The length of floors vec must be
+    /// less than or equal to the number of free slots, this is checked but not enforced, surplus
+    /// humans will simply cease to exist - sorry.
+    pub fn add(&mut self, from_floor: i32, floors: Vec<FloorNum>) {
         for floor in floors.iter() {
-            // TODO: get a real duration
-            let result = self.add_single_(floor.0, Duration::from_secs(10), false);
+            let patience = ride_patience(from_floor, floor.0);
+            let result = self.add_single_(floor.0, patience, false);
             if !result {
                 error!(
                     "Failed to add all humans to lift store, expected there to always be enough space"
@@ -151,13 +228,17 @@ impl LiftHumanStore {
         self.sort_slots();
     }
 
-    pub fn take_for_floor(&mut self, floor_num: i32) -> Vec<Duration> {
+    pub fn take_for_floor(&mut self, floor_num: i32) -> Vec<RideReport> {
         let mut taken = vec![];
         for slot in self.slots.iter_mut() {
             let mut clear = false;
             if let Some(stored_human) = &slot.0 {
                 if stored_human.destination_floor == floor_num {
-                    taken.push(stored_human.patience_timer.remaining());
+                    taken.push(RideReport {
+                        patience_remaining: stored_human.patience_timer.remaining(),
+                        patience_fraction: stored_human.patience_fraction(),
+                        comfort: stored_human.ride.score(),
+                    });
                     clear = true;
                 }
             }
@@ -168,52 +249,174 @@ impl LiftHumanStore {
         taken
     }
 
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// Who's in each slot, in the order they're shown
+    pub fn passengers(&self) -> impl Iterator<Item = Option<&StoredHuman>> {
+        self.slots.iter().map(|slot| slot.0.as_ref())
+    }
+
     pub fn free_capacity(&self) -> usize {
         self.slots.iter().filter(|slot| slot.0.is_none()).count()
     }
 
-    pub fn update_system(mut humans: ResMut<Self>, time: Res<Time>) {
-        for slot in humans.slots.iter_mut() {
+    /// Wear down every rider's patience. Those who run out give up on the lift and leave it,
+    /// undelivered and without tipping, and their destinations are returned
+    fn tick_patience(&mut self, delta: Duration) -> Vec<i32> {
+        let mut abandoned = vec![];
+        for slot in self.slots.iter_mut() {
             if let Some(human) = &mut slot.0 {
-                human.patience_timer.tick(time.delta());
+                human.patience_timer.tick(delta);
+                if human.patience_timer.finished() {
+                    abandoned.push(human.destination_floor);
+                    slot.0 = None;
+                }
             }
         }
+        if !abandoned.is_empty() {
+            self.sort_slots();
+        }
+        abandoned
     }
-    pub fn draw_system(
-        mut humans: Res<Self>,
-        mut contexts: EguiContexts,
-        texture_assets: Res<TextureAssets>,
-        mut texture_ids: Local<(egui::TextureId, egui::TextureId)>,
-        mut is_initialized: Local<bool>,
+
+    pub fn update_system(
+        mut humans: ResMut<Self>,
+        time: Res<Time>,
+        game_central_info: Res<GameCentralInfo>,
+        mut session_log: ResMut<SessionLog>,
     ) {
-        if EGUI_UI_ENABLED {
-            if !*is_initialized {
-                *is_initialized = true;
-                *texture_ids = (
-                    contexts.add_image(texture_assets.human_icon_on.clone_weak()),
-                    contexts.add_image(texture_assets.human_icon_off.clone_weak()),
-                );
+        for destination_floor in humans.tick_patience(time.delta()) {
+            info!(
+                "A rider for {} ran out of patience and left the lift",
+                floor_num_pretty_str(destination_floor)
+            );
+            session_log.push(SessionEvent::abandon(
+                game_central_info.time.elapsed_secs(),
+                destination_floor,
+            ));
+        }
+    }
+    pub fn ride_comfort_system(
+        mut humans: ResMut<Self>,
+        lift_query: Query<(&LiftMotion, &LinearVelocity)>,
+        time: Res<Time>,
+    ) {
+        for (motion, velocity) in lift_query.iter() {
+            let limits = ComfortLimits::new(velocity.max_accel(), time.delta_seconds());
+            for slot in humans.slots.iter_mut() {
+                if let Some(human) = &mut slot.0 {
+                    human.ride.record(motion, &limits);
+                }
             }
-            let frame = ui::default_frame();
-            let num_columns = 3;
-            egui::Window::new("Held Humans")
-                .movable(false)
-                //.resizable(false)
-                .anchor(Align2::RIGHT_TOP, egui::Vec2::ZERO)
-                .title_bar(false)
-                .frame(frame)
-                .show(contexts.ctx_mut(), |ui| {
-                    egui::Grid::new("held human slots")
-                        .num_columns(num_columns)
-                        .show(ui, |ui| {
-                            for (i, slot) in humans.slots.iter().enumerate() {
-                                if i % num_columns == 0 {
-                                    ui.end_row();
-                                }
-                                slot.ui_component(ui, &texture_ids);
-                            }
-                        });
-                });
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEP: f32 = 1.0 / 64.0;
+
+    #[test]
+    fn test_comfort_score() {
+        // Comfortable up to 100 acceleration and 625 jerk, worst at 200 and 1000
+        let limits = ComfortLimits::new(100.0, STEP);
+        let mut ride = RideComfort::default();
+        assert_eq!(ride.score(), 1.0);
+        ride.record(
+            &LiftMotion {
+                acceleration: -30.0,
+                jerk: 100.0,
+                ..default()
+            },
+            &limits,
+        );
+        assert_eq!(ride.score(), 1.0);
+        ride.record(
+            &LiftMotion {
+                acceleration: 150.0,
+                jerk: -1000.0,
+                ..default()
+            },
+            &limits,
+        );
+        assert_eq!(ride.score(), 0.25);
+        // Peaks stick even once the ride smooths out
+        ride.record(&LiftMotion::default(), &limits);
+        assert_eq!(ride.score(), 0.25);
+    }
+
+    /// Drive the lift through [accelerations], each held for a number of steps, as the lift's
+    /// acceleration limit would give
+    fn ride_of_profile(accelerations: &[(f32, usize)]) -> RideComfort {
+        let limits = ComfortLimits::new(100.0, STEP);
+        let mut motion = LiftMotion::default();
+        let mut ride = RideComfort::default();
+        let mut velocity = 0.0;
+        for (acceleration, steps) in accelerations {
+            for _ in 0..*steps {
+                velocity += acceleration * STEP;
+                motion.update(velocity, STEP);
+                ride.record(&motion, &limits);
+            }
+        }
+        ride
+    }
+
+    #[test]
+    fn test_start_cruise_stop_is_comfortable() {
+        // Up to full speed at full acceleration, cruise for a couple of seconds, then brake
+        let ride = ride_of_profile(&[(100.0, 64), (0.0, 128), (-100.0, 64), (0.0, 32)]);
+        assert!(ride.score() > 0.95, "{:?}", ride);
+    }
+
+    #[test]
+    fn test_swinging_the_lift_about_is_not() {
+        let ride = ride_of_profile(&[(100.0, 64), (-100.0, 64), (100.0, 64), (-100.0, 64)]);
+        assert!(ride.score() < 0.6, "{:?}", ride);
+    }
+
+    #[test]
+    fn test_impatient_riders_leave() {
+        let mut store = LiftHumanStore::create();
+        assert!(store.add_single(3, Duration::from_secs(5)));
+        assert!(store.add_single(7, Duration::from_secs(20)));
+        assert!(store.tick_patience(Duration::from_secs(4)).is_empty());
+        assert_eq!(store.tick_patience(Duration::from_secs(2)), vec![3]);
+        assert_eq!(store.free_capacity(), store.max_size() - 1);
+        // Gone, so not delivered either
+        assert!(store.take_for_floor(3).is_empty());
+        assert_eq!(store.take_for_floor(7).len(), 1);
+    }
+
+    #[test]
+    fn test_longer_trips_get_more_patience() {
+        assert_eq!(ride_patience(1, 2), ride_patience(2, 1));
+        assert!(ride_patience(1, 10) > ride_patience(1, 2));
+        let mut store = LiftHumanStore::create();
+        store.add(1, vec![FloorNum(2), FloorNum(10)]);
+        let mut left = store.tick_patience(ride_patience(1, 2));
+        assert_eq!(left, vec![2]);
+        left = store.tick_patience(ride_patience(1, 10) - ride_patience(1, 2));
+        assert_eq!(left, vec![10]);
+    }
+
+    #[test]
+    fn test_patience_colour() {
+        assert_eq!(patience_rgb(1.0), [0.0, 1.0, 0.0]);
+        assert_eq!(patience_rgb(0.5), [1.0, 1.0, 0.0]);
+        assert_eq!(patience_rgb(0.0), [1.0, 0.0, 0.0]);
+        assert_eq!(patience_rgb(-1.0), [1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_patience_warning_flashes() {
+        assert!(!patience_warning_visible(0.5, 0.0));
+        assert!(patience_warning_visible(0.1, 0.0));
+        assert!(!patience_warning_visible(0.1, 0.3));
+        assert!(patience_warning_visible(0.1, 0.5));
+    }
+}
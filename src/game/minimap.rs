@@ -0,0 +1,331 @@
+//! A vertical map of the whole building on the overlay, showing how many humans are waiting on
+//! each floor (or each few floors, in tall buildings), what each floor is doing, and where the
+//! lift is. Clicking a floor pans there.
+
+use crate::camera::{CameraMode, RENDER_LAYER_OVERLAY};
+use crate::core::{InScreenSpaceLocation, ScreenSpaceAnchor, With2DScale};
+use crate::game::floors::{self, floor_num_pretty_str, FloorNum, Floors};
+use crate::game::game::{DayScoped, DayScopedResourceExt, Lift, LinearVelocity};
+use crate::game::human_store::{Human, HumanStore};
+use crate::game::spawn_simulation::{FloorActivity, FloorSpawnManager};
+use crate::game::speed_selector::OverlayGizmos;
+use crate::{GameState, InputAction};
+use bevy::prelude::*;
+use bevy::render::view::RenderLayers;
+use bevy::utils::HashMap;
+use bevy::window::PrimaryWindow;
+use leafwing_input_manager::action_state::ActionState;
+
+const MINIMAP_SIZE: Vec2 = Vec2::new(90.0, 320.0);
+/// Space above the top floor and below the bottom one
+const MINIMAP_PADDING: f32 = 16.0;
+const MINIMAP_Z: f32 = 10.0;
+const BACKGROUND_COLOUR: Color = Color::rgba(0.1, 0.1, 0.1, 0.7);
+const STATUS_SIZE: f32 = 10.0;
+/// Tall buildings only get a label every few floors, so labels are at least this far apart
+const LABEL_FONT_SIZE: f32 = 14.0;
+const STATUS_X: f32 = -28.0;
+const LABEL_X: f32 = -8.0;
+const QUEUE_X: f32 = 26.0;
+const LIFT_MARKER_X: f32 = -40.0;
+const LIFT_MARKER_SIZE: Vec2 = Vec2::new(8.0, 4.0);
+/// Below this speed the lift's direction isn't shown
+const DIRECTION_MIN_SPEED: f32 = 1.0;
+
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::PlayingDay),
+            spawn_minimap.after(floors::build_floor_map),
+        )
+        .add_systems(
+            Update,
+            (
+                queue_count_system,
+                floor_activity_system,
+                lift_marker_system,
+                minimap_click_system,
+            )
+                .run_if(in_state(GameState::PlayingDay)),
+        )
+        .init_day_resource::<MinimapHovered>();
+    }
+}
+
+/// Whether the cursor's over the minimap, so clicks there aren't taken as clicks on the world
+#[derive(Resource, Debug, Default)]
+pub struct MinimapHovered(pub bool);
+
+/// The minimap's root, maps the building's floors onto its height
+#[derive(Component, Debug)]
+struct Minimap {
+    world_min_y: f32,
+    world_max_y: f32,
+}
+
+impl Minimap {
+    fn of_floors(floors: &Floors) -> Self {
+        let (world_min_y, world_max_y) = floors
+            .floor_y_positions
+            .iter()
+            .fold((f32::MAX, f32::MIN), |(min, max), (_, y)| {
+                (min.min(*y), max.max(*y))
+            });
+        Self {
+            world_min_y,
+            world_max_y,
+        }
+    }
+
+    fn to_minimap_y(&self, world_y: f32) -> f32 {
+        let range = self.world_max_y - self.world_min_y;
+        let t = if range > 0.0 {
+            (world_y - self.world_min_y) / range
+        } else {
+            0.5
+        };
+        let half_height = MINIMAP_SIZE.y / 2.0 - MINIMAP_PADDING;
+        -half_height + t.clamp(0.0, 1.0) * half_height * 2.0
+    }
+}
+
+/// Humans waiting across these floors, the labelled one and those up to the next label
+#[derive(Component, Debug)]
+struct MinimapQueueText(Vec<i32>);
+
+#[derive(Component, Debug)]
+struct MinimapFloorActivity(i32);
+
+#[derive(Component, Debug)]
+struct MinimapLift;
+
+/// Distance between neighbouring rows for [floor_count] floors
+fn row_spacing(floor_count: usize) -> f32 {
+    (MINIMAP_SIZE.y - MINIMAP_PADDING * 2.0) / floor_count.saturating_sub(1).max(1) as f32
+}
+
+/// Label every how many floors, so labels don't overlap
+fn label_every(floor_count: usize) -> usize {
+    ((LABEL_FONT_SIZE / row_spacing(floor_count)).ceil() as usize).max(1)
+}
+
+fn activity_colour(activity: FloorActivity) -> Color {
+    match activity {
+        FloorActivity::Idle => Color::DARK_GRAY,
+        FloorActivity::Sink => Color::ORANGE,
+        FloorActivity::Source => Color::CYAN,
+        FloorActivity::Both => Color::PURPLE,
+    }
+}
+
+fn spawn_minimap(mut commands: Commands, floors: Res<Floors>) {
+    let render_layers = RenderLayers::layer(RENDER_LAYER_OVERLAY);
+    let minimap = Minimap::of_floors(&floors);
+    let mut floor_rows: Vec<(i32, f32)> = floors
+        .floor_y_positions
+        .iter()
+        .map(|(floor_num, world_y)| (*floor_num, minimap.to_minimap_y(*world_y)))
+        .collect();
+    floor_rows.sort_by_key(|(floor_num, _)| *floor_num);
+    let label_every = label_every(floor_rows.len());
+    let status_size = STATUS_SIZE.min(row_spacing(floor_rows.len()) * 0.8);
+    let text_style = TextStyle {
+        font_size: LABEL_FONT_SIZE,
+        color: Color::WHITE,
+        ..default()
+    };
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: BACKGROUND_COLOUR,
+                custom_size: Some(MINIMAP_SIZE),
+                ..default()
+            },
+            transform: Transform::from_translation(Vec3::new(0.0, 0.0, MINIMAP_Z)),
+            ..default()
+        })
+        .insert(Name::new("Minimap"))
+        .insert(minimap)
+        .insert(DayScoped)
+        .insert(render_layers.clone())
+        .insert(InScreenSpaceLocation::new(
+            ScreenSpaceAnchor::Left,
+            MINIMAP_SIZE.x / 2.0 + 20.0,
+        ))
+        .insert(With2DScale::new(1.0))
+        .with_children(|parent| {
+            for (floor_num, y) in floor_rows.iter().copied() {
+                parent
+                    .spawn(SpriteBundle {
+                        sprite: Sprite {
+                            color: activity_colour(FloorActivity::Idle),
+                            custom_size: Some(Vec2::splat(status_size)),
+                            ..default()
+                        },
+                        transform: Transform::from_xyz(STATUS_X, y, 0.1),
+                        ..default()
+                    })
+                    .insert(MinimapFloorActivity(floor_num))
+                    .insert(render_layers.clone());
+            }
+            for group in floor_rows.chunks(label_every) {
+                let (floor_num, y) = group[0];
+                parent
+                    .spawn(Text2dBundle {
+                        text: Text::from_section(
+                            floor_num_pretty_str(floor_num),
+                            text_style.clone(),
+                        ),
+                        transform: Transform::from_xyz(LABEL_X, y, 0.1),
+                        ..default()
+                    })
+                    .insert(render_layers.clone());
+                parent
+                    .spawn(Text2dBundle {
+                        text: Text::from_section("", text_style.clone()),
+                        transform: Transform::from_xyz(QUEUE_X, y, 0.1),
+                        ..default()
+                    })
+                    .insert(MinimapQueueText(
+                        group.iter().map(|(floor_num, _)| *floor_num).collect(),
+                    ))
+                    .insert(render_layers.clone());
+            }
+            parent
+                .spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::WHITE,
+                        custom_size: Some(LIFT_MARKER_SIZE),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(LIFT_MARKER_X, 0.0, 0.2),
+                    ..default()
+                })
+                .insert(MinimapLift)
+                .insert(render_layers.clone());
+        });
+}
+
+fn queue_count_system(
+    store_query: Query<(Entity, &FloorNum), With<HumanStore>>,
+    human_query: Query<&Parent, With<Human>>,
+    mut text_query: Query<(&mut Text, &MinimapQueueText)>,
+) {
+    let mut waiting: HashMap<Entity, usize> = HashMap::new();
+    for parent in human_query.iter() {
+        *waiting.entry(parent.get()).or_default() += 1;
+    }
+    let waiting_per_floor: HashMap<i32, usize> = store_query
+        .iter()
+        .map(|(entity, floor_num)| (floor_num.0, waiting.get(&entity).copied().unwrap_or(0)))
+        .collect();
+    for (mut text, queue_text) in text_query.iter_mut() {
+        let count = match queue_text
+            .0
+            .iter()
+            .filter_map(|floor_num| waiting_per_floor.get(floor_num))
+            .sum::<usize>()
+        {
+            0 => String::new(),
+            count => count.to_string(),
+        };
+        if text.sections[0].value != count {
+            text.sections[0].value = count;
+        }
+    }
+}
+
+fn floor_activity_system(
+    floor_spawn_manager: Res<FloorSpawnManager>,
+    mut activity_query: Query<(&mut Sprite, &MinimapFloorActivity)>,
+) {
+    for (mut sprite, floor_activity) in activity_query.iter_mut() {
+        let colour = activity_colour(floor_spawn_manager.activity(FloorNum(floor_activity.0)));
+        if sprite.color != colour {
+            sprite.color = colour;
+        }
+    }
+}
+
+fn lift_marker_system(
+    lift_query: Query<(&Transform, &LinearVelocity), With<Lift>>,
+    minimap_query: Query<&Minimap>,
+    mut marker_query: Query<(&mut Transform, &GlobalTransform), (With<MinimapLift>, Without<Lift>)>,
+    mut gizmos: Gizmos<OverlayGizmos>,
+) {
+    let (Ok((lift_transform, velocity)), Ok(minimap)) =
+        (lift_query.get_single(), minimap_query.get_single())
+    else {
+        return;
+    };
+    for (mut marker_transform, marker_global) in marker_query.iter_mut() {
+        marker_transform.translation.y = minimap.to_minimap_y(lift_transform.translation.y);
+        if velocity.velocity.abs() >= DIRECTION_MIN_SPEED {
+            let start = marker_global.translation().truncate() - Vec2::X * LIFT_MARKER_SIZE.x;
+            let end = start + Vec2::Y * 12.0 * velocity.velocity.signum();
+            gizmos.arrow_2d(start, end, Color::WHITE);
+        }
+    }
+}
+
+/// The overlay camera sits at the origin with a scale of 1, so its world space is the window's
+/// logical pixels with the origin in the middle and y up
+fn minimap_click_system(
+    inputs: Query<&ActionState<InputAction>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    minimap_query: Query<(&Minimap, &GlobalTransform)>,
+    floors: Res<Floors>,
+    mut hovered: ResMut<MinimapHovered>,
+    mut camera_mode: ResMut<CameraMode>,
+) {
+    let (Ok(window), Ok((minimap, minimap_transform))) =
+        (window_query.get_single(), minimap_query.get_single())
+    else {
+        return;
+    };
+    let Some(cursor) = window.cursor_position() else {
+        hovered.0 = false;
+        return;
+    };
+    let cursor_overlay = Vec2::new(
+        cursor.x - window.width() / 2.0,
+        window.height() / 2.0 - cursor.y,
+    );
+    let scale = minimap_transform.compute_transform().scale.y;
+    let minimap_rect = Rect::from_center_size(
+        minimap_transform.translation().truncate(),
+        MINIMAP_SIZE * scale,
+    );
+    hovered.0 = minimap_rect.contains(cursor_overlay);
+    let clicked = inputs.get_single().map_or(false, |inputs| {
+        inputs.just_pressed(&InputAction::MouseLClick)
+    });
+    if !hovered.0 || !clicked {
+        return;
+    }
+    let minimap_y = (cursor_overlay.y - minimap_rect.center().y) / scale;
+    if let Some((floor_num, world_y)) = floors.floor_y_positions.iter().min_by(|(_, a), (_, b)| {
+        let distance = |world_y: &f32| (minimap.to_minimap_y(*world_y) - minimap_y).abs();
+        distance(a).total_cmp(&distance(b))
+    }) {
+        info!("Minimap panning to floor {}", floor_num);
+        *camera_mode = CameraMode::Pan { y: *world_y };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_labels_thin_out_for_tall_buildings() {
+        assert_eq!(label_every(10), 1);
+        for floor_count in [30, 100] {
+            let every = label_every(floor_count);
+            assert!(every > 1);
+            assert!(row_spacing(floor_count) * every as f32 >= LABEL_FONT_SIZE);
+        }
+    }
+}
@@ -1,18 +1,31 @@
 use bevy::app::{App, Plugin};
 
+pub mod campaign;
+#[cfg(feature = "debug-tools")]
+pub mod debug;
+pub mod endless;
+mod floor_select;
 mod floors;
 mod game;
 pub mod game_clock;
+pub mod ghost;
+mod hud;
 mod human_store;
 mod lift;
-mod overlay;
+mod minimap;
+mod motion_planning;
+mod pause_menu;
+pub mod replay;
+pub mod response_curve;
+pub mod seed;
 pub mod spawn_simulation;
 mod speed_selector;
+pub mod telemetry;
+mod time_scale;
 mod ui;
-mod ui_b;
-mod world_gen;
+pub mod world_gen;
 
-pub use floors::FloorNum;
+pub use floors::{floor_num_pretty_str, FloorNum};
 
 pub struct CoreGamePlugin;
 
@@ -22,8 +35,16 @@ impl Plugin for CoreGamePlugin {
             game::GamePlugin,
             speed_selector::SpeedSelectorPlugin,
             ui::GameUiPlugin,
-            ui_b::UIBPlugin,
-            overlay::OverlayPlugin,
+            hud::HudPlugin,
+            time_scale::TimeScalePlugin,
+            pause_menu::PauseMenuPlugin,
+            floor_select::FloorSelectPlugin,
+            telemetry::TelemetryPlugin,
+            replay::ReplayPlugin,
+            ghost::GhostPlugin,
+            minimap::MinimapPlugin,
+            campaign::CampaignPlugin,
+            endless::EndlessPlugin,
         ));
     }
 }
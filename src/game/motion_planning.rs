@@ -0,0 +1,73 @@
+//! Drives the lift to a floor automatically, for the assisted driving mode
+
+/// Distance from the target at which the lift is considered to have arrived
+const ARRIVAL_TOLERANCE: f32 = 0.5;
+/// Plan to brake at this fraction of the lift's true maximum, leaving some headroom for the
+/// acceleration being applied in discrete steps
+const BRAKING_MARGIN: f32 = 0.8;
+
+#[derive(Debug, Clone, Copy)]
+pub struct MotionLimits {
+    pub max_speed: f32,
+    pub max_accel: f32,
+}
+
+/// The velocity to aim for this step to get from [position] to [target] as quickly as possible
+/// without overshooting. This is a trapezoidal profile: accelerate up to [max_speed], cruise,
+/// then brake so as to reach zero velocity exactly at [target].
+pub fn velocity_towards(
+    position: f32,
+    target: f32,
+    limits: MotionLimits,
+    delta_seconds: f32,
+) -> f32 {
+    let distance = target - position;
+    if distance.abs() < ARRIVAL_TOLERANCE {
+        return 0.0;
+    }
+    // v^2 = u^2 + 2as, solved for the fastest u from which we can still stop in the distance
+    let braking_speed = (2.0 * limits.max_accel * BRAKING_MARGIN * distance.abs()).sqrt();
+    // Don't step past the target in a single update either
+    let step_speed = distance.abs() / delta_seconds;
+    let speed = limits.max_speed.min(braking_speed).min(step_speed);
+    speed * distance.signum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Mirrors how the lift applies its acceleration limit
+    fn step(velocity: f32, target_velocity: f32, max_accel: f32, dt: f32) -> f32 {
+        let accel = ((target_velocity - velocity) / dt).clamp(-max_accel, max_accel);
+        velocity + accel * dt
+    }
+
+    fn simulate(start: f32, target: f32) {
+        let limits = MotionLimits {
+            max_speed: 100.0,
+            max_accel: 100.0,
+        };
+        let dt = 1.0 / 64.0;
+        let mut position = start;
+        let mut velocity = 0.0;
+        for _ in 0..(64 * 30) {
+            let target_velocity = velocity_towards(position, target, limits, dt);
+            velocity = step(velocity, target_velocity, limits.max_accel, dt);
+            assert!(velocity.abs() <= limits.max_speed + 1e-3);
+            position += velocity * dt;
+            // Never past the target
+            assert!((target - position) * (target - start).signum() > -ARRIVAL_TOLERANCE);
+        }
+        assert!((target - position).abs() < ARRIVAL_TOLERANCE);
+        assert!(velocity.abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_reaches_target_without_overshoot() {
+        simulate(0.0, 600.0);
+        simulate(600.0, -60.0);
+        simulate(0.0, 60.0);
+        simulate(0.0, -3.0);
+    }
+}
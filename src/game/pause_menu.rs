@@ -0,0 +1,105 @@
+use crate::game::endless::EndlessRun;
+use crate::game::time_scale::TimeScale;
+use crate::input_action::InputAction;
+use crate::settings::SettingsWindow;
+use crate::{GameState, PauseState};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use bevy_kira_audio::prelude::*;
+use leafwing_input_manager::action_state::ActionState;
+
+pub struct PauseMenuPlugin;
+
+impl Plugin for PauseMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            toggle_pause_system.run_if(in_state(GameState::PlayingDay)),
+        )
+        .add_systems(
+            Update,
+            pause_menu
+                .run_if(in_state(GameState::PlayingDay))
+                .run_if(in_state(PauseState::Paused)),
+        )
+        .add_systems(OnEnter(PauseState::Paused), pause)
+        .add_systems(OnExit(PauseState::Paused), resume)
+        .add_systems(OnEnter(GameState::RestartingDay), restart_day);
+    }
+}
+
+fn toggle_pause_system(
+    inputs: Query<&ActionState<InputAction>>,
+    pause_state: Res<State<PauseState>>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+) {
+    for inputs in inputs.iter() {
+        if inputs.just_pressed(&InputAction::Pause) {
+            next_pause_state.set(match pause_state.get() {
+                PauseState::Running => PauseState::Paused,
+                PauseState::Paused => PauseState::Running,
+            });
+        }
+    }
+}
+
+fn pause(mut time: ResMut<Time<Virtual>>, audio: Res<Audio>) {
+    time.pause();
+    audio.pause();
+}
+
+fn resume(mut time: ResMut<Time<Virtual>>, time_scale: Option<Res<TimeScale>>, audio: Res<Audio>) {
+    // The time scale might have been paused before the menu was opened, so leave it be if so.
+    // It's also gone already if the day was quit from the menu.
+    if !time_scale.is_some_and(|time_scale| time_scale.paused) {
+        time.unpause();
+    }
+    audio.resume();
+}
+
+fn pause_menu(
+    mut contexts: EguiContexts,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+    mut settings_window: ResMut<SettingsWindow>,
+    endless_run: Option<Res<EndlessRun>>,
+) {
+    egui::Window::new("Paused")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.style_mut().text_styles.insert(
+                egui::TextStyle::Button,
+                egui::FontId::new(23.0, egui::FontFamily::Proportional),
+            );
+            ui.vertical_centered_justified(|ui| {
+                if ui.button("Resume").clicked() {
+                    next_pause_state.set(PauseState::Running);
+                }
+                // The shift's abandonments would be forgotten, getting out of a game over
+                let restart_button = ui
+                    .add_enabled(endless_run.is_none(), egui::Button::new("Restart Day"))
+                    .on_disabled_hover_text("Shifts of an endless run can't be restarted");
+                if restart_button.clicked() {
+                    info!("Restart Day");
+                    next_pause_state.set(PauseState::Running);
+                    next_state.set(GameState::RestartingDay);
+                }
+                if ui.button("Settings").clicked() {
+                    settings_window.open = !settings_window.open;
+                }
+                if ui.button("Quit to Menu").clicked() {
+                    info!("Quit to Menu");
+                    next_pause_state.set(PauseState::Running);
+                    next_state.set(GameState::PlayingMenu);
+                }
+            });
+        });
+}
+
+/// [GameState::RestartingDay] only exists so that leaving and re-entering [GameState::PlayingDay]
+/// runs the usual exit and enter schedules
+fn restart_day(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::PlayingDay);
+}
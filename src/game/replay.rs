@@ -0,0 +1,651 @@
+//! Recording and playback of days.
+//!
+//! As the day's simulation is deterministic given its [DaySeed] (see [DaySimulationSet]), all
+//! a replay needs is the seed, the day's clock config, and the lift's [TargetVelocity] for each
+//! [FixedUpdate] tick. The lift's trajectory is stored too, so replays can be raced against as a
+//! ghost without having to simulate them, along with the building it was recorded in.
+
+use crate::game::endless::EndlessRun;
+use crate::game::floor_select;
+use crate::game::floors::Floors;
+use crate::game::game::{DayScopedResourceExt, DaySimulationSet, Lift};
+use crate::game::game_clock::GameTimeConfig;
+use crate::game::seed::DaySeed;
+use crate::game::speed_selector::TargetVelocity;
+use crate::game::telemetry::{DayEndedEvent, SessionLog};
+use crate::game::time_scale::TimeScale;
+use crate::game::ui::GameCentralInfo;
+use crate::game::world_gen::{Class, Level, LevelKind};
+use crate::{GameState, InputAction};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use leafwing_input_manager::action_state::ActionState;
+use std::fmt::Formatter;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const REPLAY_MAGIC: &[u8; 4] = b"GUPR";
+pub const REPLAY_VERSION: u16 = 1;
+pub const REPLAYS_DIR: &str = "replays";
+const REPLAY_EXTENSION: &str = "replay";
+/// More floors than any building has, past which a replay is taken to be corrupt
+const MAX_FLOOR_COUNT: usize = 100;
+
+/// How many times faster than normal a replay runs while seeking forwards
+const SEEK_SPEED: f32 = 32.0;
+/// Virtual time's max delta is raised while seeking, else it caps how fast we can go
+const SEEK_MAX_DELTA: Duration = Duration::from_secs(1);
+/// Bevy's default for [Time<Virtual>]'s max delta
+const DEFAULT_MAX_DELTA: Duration = Duration::from_millis(250);
+
+/// Actions whose pressed state is recorded each tick, by bit position. They don't affect
+/// playback (that's all in [ReplayTick::target_velocity]) but show what the player was doing
+static RECORDED_ACTIONS: [InputAction; 3] =
+    [InputAction::Up, InputAction::Down, InputAction::MouseLClick];
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::PlayingDay), start_playback_system)
+            .add_systems(
+                FixedUpdate,
+                (playback_system, record_system)
+                    .chain()
+                    .after(floor_select::assisted_drive_system)
+                    .in_set(DaySimulationSet::Input),
+            )
+            .add_systems(
+                Update,
+                (
+                    save_on_day_end_system.run_if(not(resource_exists::<EndlessRun>)),
+                    seek_system,
+                    replay_viewer_system,
+                )
+                    .run_if(in_state(GameState::PlayingDay)),
+            )
+            .add_systems(
+                OnExit(GameState::PlayingDay),
+                save_on_exit_system.run_if(not(resource_exists::<EndlessRun>)),
+            )
+            .init_day_resource::<ReplayRecorder>()
+            .remove_on_day_exit::<ReplayPlayback>();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplayTick {
+    pub target_velocity: f32,
+    /// Bitset of [RECORDED_ACTIONS]
+    pub inputs: u16,
+}
+
+const TICK_SIZE: usize = 6;
+
+/// Where the lift was at a tick, and how the day was going
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrajectorySample {
+    pub lift_y: f32,
+    /// Total humans delivered so far
+    pub deliveries: u32,
+}
+
+const SAMPLE_SIZE: usize = 8;
+
+impl ReplayTick {
+    pub fn pressed_actions(&self) -> impl Iterator<Item = &InputAction> + '_ {
+        RECORDED_ACTIONS
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.inputs & (1 << i) != 0)
+            .map(|(_, action)| action)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    pub time_per_day: Duration,
+    pub working_hours: Range<u8>,
+    /// See [crate::game::floors::Floors::fingerprint], 0 if unknown
+    pub building: u64,
+    /// Regular floors in the building
+    pub floor_count: usize,
+    pub ticks: Vec<ReplayTick>,
+    /// One sample per tick
+    pub trajectory: Vec<TrajectorySample>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(std::io::Error),
+    NotAReplay,
+    UnsupportedVersion(u16),
+    Truncated,
+}
+
+impl std::fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "ReplayError({})", error),
+            Self::NotAReplay => write!(f, "ReplayError(not a replay file)"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "ReplayError(unsupported version {})", version)
+            }
+            Self::Truncated => write!(f, "ReplayError(truncated)"),
+        }
+    }
+}
+
+impl From<std::io::Error> for ReplayError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// Reads little endian values off the front of a byte slice
+struct ByteReader<'a>(&'a [u8]);
+
+impl<'a> ByteReader<'a> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], ReplayError> {
+        if self.0.len() < N {
+            return Err(ReplayError::Truncated);
+        }
+        let (taken, rest) = self.0.split_at(N);
+        self.0 = rest;
+        Ok(taken.try_into().unwrap())
+    }
+    fn u8(&mut self) -> Result<u8, ReplayError> {
+        Ok(u8::from_le_bytes(self.take()?))
+    }
+    fn u16(&mut self) -> Result<u16, ReplayError> {
+        Ok(u16::from_le_bytes(self.take()?))
+    }
+    fn u32(&mut self) -> Result<u32, ReplayError> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+    fn u64(&mut self) -> Result<u64, ReplayError> {
+        Ok(u64::from_le_bytes(self.take()?))
+    }
+    fn f32(&mut self) -> Result<f32, ReplayError> {
+        Ok(f32::from_le_bytes(self.take()?))
+    }
+}
+
+impl Replay {
+    pub fn time_config(&self) -> GameTimeConfig {
+        GameTimeConfig::new(self.time_per_day, self.working_hours.clone())
+    }
+
+    /// The building the replay was recorded in
+    pub fn level(&self) -> Level {
+        Level::with_floor_count(LevelKind::Hotel, Class::One, self.floor_count)
+    }
+
+    /// Deliveries made by the end of the replay, if it has a trajectory
+    pub fn final_deliveries(&self) -> Option<u32> {
+        self.trajectory.last().map(|sample| sample.deliveries)
+    }
+
+    /// Layout, all little endian: magic, version: u16, seed: u64, time per day in millis: u64,
+    /// start hour: u8, end hour: u8, building: u64, floor count: u32, tick count: u32, then per
+    /// tick target velocity: f32 and inputs: u16, then sample count: u32 and per sample lift y:
+    /// f32 and deliveries: u32
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(
+            44 + self.ticks.len() * TICK_SIZE + self.trajectory.len() * SAMPLE_SIZE,
+        );
+        bytes.extend_from_slice(REPLAY_MAGIC);
+        bytes.extend_from_slice(&REPLAY_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&(self.time_per_day.as_millis() as u64).to_le_bytes());
+        bytes.push(self.working_hours.start);
+        bytes.push(self.working_hours.end);
+        bytes.extend_from_slice(&self.building.to_le_bytes());
+        bytes.extend_from_slice(&(self.floor_count as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.ticks.len() as u32).to_le_bytes());
+        for tick in self.ticks.iter() {
+            bytes.extend_from_slice(&tick.target_velocity.to_le_bytes());
+            bytes.extend_from_slice(&tick.inputs.to_le_bytes());
+        }
+        bytes.extend_from_slice(&(self.trajectory.len() as u32).to_le_bytes());
+        for sample in self.trajectory.iter() {
+            bytes.extend_from_slice(&sample.lift_y.to_le_bytes());
+            bytes.extend_from_slice(&sample.deliveries.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn of_bytes(bytes: &[u8]) -> Result<Self, ReplayError> {
+        let mut reader = ByteReader(bytes);
+        if reader.take::<4>().ok().as_ref() != Some(REPLAY_MAGIC) {
+            return Err(ReplayError::NotAReplay);
+        }
+        let version = reader.u16()?;
+        if version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+        let seed = reader.u64()?;
+        let time_per_day = Duration::from_millis(reader.u64()?);
+        let working_hours = reader.u8()?..reader.u8()?;
+        if working_hours.is_empty() || working_hours.end > 24 {
+            return Err(ReplayError::NotAReplay);
+        }
+        let building = reader.u64()?;
+        let floor_count = reader.u32()? as usize;
+        if !(1..=MAX_FLOOR_COUNT).contains(&floor_count) {
+            return Err(ReplayError::NotAReplay);
+        }
+        let num_ticks = reader.u32()? as usize;
+        let mut ticks = Vec::with_capacity(num_ticks.min(reader.0.len() / TICK_SIZE));
+        for _ in 0..num_ticks {
+            ticks.push(ReplayTick {
+                target_velocity: reader.f32()?,
+                inputs: reader.u16()?,
+            });
+        }
+        let num_samples = reader.u32()? as usize;
+        let mut trajectory = Vec::with_capacity(num_samples.min(reader.0.len() / SAMPLE_SIZE));
+        for _ in 0..num_samples {
+            trajectory.push(TrajectorySample {
+                lift_y: reader.f32()?,
+                deliveries: reader.u32()?,
+            });
+        }
+        Ok(Self {
+            seed,
+            time_per_day,
+            working_hours,
+            building,
+            floor_count,
+            ticks,
+            trajectory,
+        })
+    }
+
+    pub fn save(&self) -> Result<PathBuf, ReplayError> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        fs::create_dir_all(REPLAYS_DIR)?;
+        let path = Path::new(REPLAYS_DIR).join(self.file_name(timestamp));
+        fs::write(&path, self.to_bytes())?;
+        Ok(path)
+    }
+
+    /// Starts with [Replay::file_prefix], so runs of a day can be found without reading them
+    fn file_name(&self, timestamp: u64) -> String {
+        format!(
+            "{}{}.{}",
+            Self::file_prefix(self.seed, self.building),
+            timestamp,
+            REPLAY_EXTENSION
+        )
+    }
+
+    fn file_prefix(seed: u64, building: u64) -> String {
+        format!("seed_{}_building_{:016x}_", seed, building)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ReplayError> {
+        Self::of_bytes(&fs::read(path)?)
+    }
+
+    /// Saved replays, oldest first
+    pub fn list_saved() -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = fs::read_dir(REPLAYS_DIR)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| path.extension().is_some_and(|ext| ext == REPLAY_EXTENSION))
+                    .collect()
+            })
+            .unwrap_or_default();
+        paths.sort();
+        paths
+    }
+
+    /// Saved replays of [seed] in [building], going by their file names
+    pub fn list_saved_of(seed: u64, building: u64) -> Vec<PathBuf> {
+        let prefix = Self::file_prefix(seed, building);
+        Self::list_saved()
+            .into_iter()
+            .filter(|path| {
+                path.file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with(&prefix))
+            })
+            .collect()
+    }
+}
+
+/// The current day's inputs, saved as a [Replay] when the day ends (or is left early)
+#[derive(Resource, Debug, Default)]
+pub struct ReplayRecorder {
+    ticks: Vec<ReplayTick>,
+    trajectory: Vec<TrajectorySample>,
+    saved: bool,
+}
+
+/// A replay waiting for the next day to start, set up by [start_replay]
+#[derive(Resource, Debug)]
+struct PendingReplay {
+    replay: Replay,
+    seek_to: Option<usize>,
+}
+
+/// Present while the current day is a replay rather than being played
+#[derive(Resource, Debug)]
+pub struct ReplayPlayback {
+    pub replay: Replay,
+    /// The next tick to be played
+    pub tick: usize,
+    seek_to: Option<usize>,
+}
+
+/// Set things up so the next day started plays [replay], fast forwarding to [seek_to] if given.
+/// The caller is responsible for then (re)starting the day.
+pub fn start_replay(
+    replay: Replay,
+    seek_to: Option<usize>,
+    commands: &mut Commands,
+    day_seed: &mut DaySeed,
+    time_config: &mut GameTimeConfig,
+    level: &mut Level,
+) {
+    info!(
+        "Starting replay of seed {} ({} ticks)",
+        replay.seed,
+        replay.ticks.len()
+    );
+    day_seed.0 = replay.seed;
+    *time_config = replay.time_config();
+    *level = replay.level();
+    commands.insert_resource(PendingReplay { replay, seek_to });
+}
+
+fn start_playback_system(mut commands: Commands, pending: Option<Res<PendingReplay>>) {
+    if let Some(pending) = pending {
+        commands.insert_resource(ReplayPlayback {
+            replay: pending.replay.clone(),
+            tick: 0,
+            seek_to: pending.seek_to,
+        });
+        commands.remove_resource::<PendingReplay>();
+    }
+}
+
+fn playback_system(
+    playback: Option<ResMut<ReplayPlayback>>,
+    mut target_velocity: ResMut<TargetVelocity>,
+) {
+    if let Some(mut playback) = playback {
+        target_velocity.0 = playback
+            .replay
+            .ticks
+            .get(playback.tick)
+            .map_or(0.0, |tick| tick.target_velocity);
+        playback.tick += 1;
+    }
+}
+
+fn record_system(
+    mut recorder: ResMut<ReplayRecorder>,
+    playback: Option<Res<ReplayPlayback>>,
+    target_velocity: Res<TargetVelocity>,
+    inputs: Query<&ActionState<InputAction>>,
+    lift_query: Query<&Transform, With<Lift>>,
+    session_log: Res<SessionLog>,
+) {
+    if playback.is_some() {
+        return;
+    }
+    let mut pressed = 0u16;
+    if let Ok(inputs) = inputs.get_single() {
+        for (i, action) in RECORDED_ACTIONS.iter().enumerate() {
+            if inputs.pressed(action) {
+                pressed |= 1 << i;
+            }
+        }
+    }
+    recorder.ticks.push(ReplayTick {
+        target_velocity: target_velocity.0,
+        inputs: pressed,
+    });
+    recorder.trajectory.push(TrajectorySample {
+        lift_y: lift_query
+            .get_single()
+            .map_or(0.0, |transform| transform.translation.y),
+        deliveries: session_log.deliveries() as u32,
+    });
+}
+
+fn save_recording(
+    recorder: &mut ReplayRecorder,
+    day_seed: &DaySeed,
+    game_central_info: &GameCentralInfo,
+    floors: Option<&Floors>,
+    level: &Level,
+) {
+    // Replays only know the floor count, so designed buildings can't be played back
+    if recorder.saved || recorder.ticks.is_empty() || level.is_designed() {
+        return;
+    }
+    let config = game_central_info.time.config();
+    let replay = Replay {
+        seed: day_seed.0,
+        time_per_day: config.time_per_day(),
+        working_hours: config.working_hours(),
+        building: floors.map_or(0, Floors::fingerprint),
+        floor_count: level.floor_count(),
+        ticks: recorder.ticks.clone(),
+        trajectory: recorder.trajectory.clone(),
+    };
+    match replay.save() {
+        Ok(path) => info!("Saved replay to {}", path.display()),
+        Err(error) => warn!("Failed to save replay: {}", error),
+    }
+    recorder.saved = true;
+}
+
+fn save_on_day_end_system(
+    mut day_ended_events: EventReader<DayEndedEvent>,
+    mut recorder: ResMut<ReplayRecorder>,
+    playback: Option<Res<ReplayPlayback>>,
+    day_seed: Res<DaySeed>,
+    game_central_info: Res<GameCentralInfo>,
+    floors: Option<Res<Floors>>,
+    level: Res<Level>,
+) {
+    if day_ended_events.read().count() > 0 && playback.is_none() {
+        save_recording(
+            &mut recorder,
+            &day_seed,
+            &game_central_info,
+            floors.as_deref(),
+            &level,
+        );
+    }
+}
+
+/// Days left early are saved too, they're the ones most likely to be attached to bug reports
+fn save_on_exit_system(
+    mut recorder: ResMut<ReplayRecorder>,
+    playback: Option<Res<ReplayPlayback>>,
+    day_seed: Res<DaySeed>,
+    game_central_info: Res<GameCentralInfo>,
+    floors: Option<Res<Floors>>,
+    level: Res<Level>,
+) {
+    if playback.is_none() {
+        save_recording(
+            &mut recorder,
+            &day_seed,
+            &game_central_info,
+            floors.as_deref(),
+            &level,
+        );
+    }
+}
+
+/// The simulation can only run forwards, so seeking back restarts the day and fast forwards
+fn seek_system(
+    mut commands: Commands,
+    playback: Option<ResMut<ReplayPlayback>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    time_scale: Res<TimeScale>,
+    mut day_seed: ResMut<DaySeed>,
+    mut time_config: ResMut<GameTimeConfig>,
+    mut level: ResMut<Level>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(mut playback) = playback else {
+        return;
+    };
+    let Some(seek_to) = playback.seek_to else {
+        return;
+    };
+    if seek_to < playback.tick {
+        start_replay(
+            playback.replay.clone(),
+            Some(seek_to),
+            &mut commands,
+            &mut day_seed,
+            &mut time_config,
+            &mut level,
+        );
+        playback.seek_to = None;
+        next_state.set(GameState::RestartingDay);
+    } else if playback.tick >= seek_to {
+        playback.seek_to = None;
+        virtual_time.set_relative_speed(time_scale.speed.factor());
+        virtual_time.set_max_delta(DEFAULT_MAX_DELTA);
+    } else {
+        virtual_time.set_relative_speed(SEEK_SPEED);
+        virtual_time.set_max_delta(SEEK_MAX_DELTA);
+    }
+}
+
+fn replay_viewer_system(
+    mut contexts: EguiContexts,
+    playback: Option<ResMut<ReplayPlayback>>,
+    fixed_time: Res<Time<Fixed>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(mut playback) = playback else {
+        return;
+    };
+    let tick_secs = fixed_time.timestep().as_secs_f32();
+    let num_ticks = playback.replay.ticks.len();
+    egui::Window::new("Replay")
+        .anchor(egui::Align2::CENTER_TOP, egui::Vec2::ZERO)
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label(format!("Seed: {}", playback.replay.seed));
+            ui.label(format!(
+                "{:.1}s / {:.1}s",
+                playback.tick as f32 * tick_secs,
+                num_ticks as f32 * tick_secs
+            ));
+            let mut scrub_tick = playback.seek_to.unwrap_or(playback.tick).min(num_ticks);
+            let response = ui.add(
+                egui::Slider::new(&mut scrub_tick, 0..=num_ticks)
+                    .show_value(false)
+                    .text("Scrub"),
+            );
+            // Only seek once the slider's let go, seeking back restarts the day each time
+            if response.drag_released() || (response.changed() && !response.dragged()) {
+                playback.seek_to = Some(scrub_tick);
+            }
+            if playback.seek_to.is_some() {
+                ui.label("Seeking...");
+            }
+            let pressed: Vec<&str> = playback
+                .replay
+                .ticks
+                .get(playback.tick.saturating_sub(1))
+                .map(|tick| tick.pressed_actions().map(|action| action.name()).collect())
+                .unwrap_or_default();
+            ui.label(format!("Inputs: {}", pressed.join(", ")));
+            if ui.button("Stop").clicked() {
+                next_state.set(GameState::PlayingMenu);
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay() -> Replay {
+        Replay {
+            seed: 0xDEADBEEF,
+            time_per_day: Duration::from_secs(240),
+            working_hours: 8..20,
+            building: 42,
+            floor_count: 14,
+            ticks: vec![
+                ReplayTick {
+                    target_velocity: 0.0,
+                    inputs: 0,
+                },
+                ReplayTick {
+                    target_velocity: -12.5,
+                    inputs: 0b101,
+                },
+            ],
+            trajectory: vec![
+                TrajectorySample {
+                    lift_y: 0.0,
+                    deliveries: 0,
+                },
+                TrajectorySample {
+                    lift_y: -0.2,
+                    deliveries: 1,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let replay = replay();
+        assert_eq!(Replay::of_bytes(&replay.to_bytes()).unwrap(), replay);
+    }
+
+    #[test]
+    fn test_file_name_identifies_day() {
+        let replay = replay();
+        let name = replay.file_name(1234);
+        assert!(name.starts_with(&Replay::file_prefix(replay.seed, replay.building)));
+        assert!(!name.starts_with(&Replay::file_prefix(replay.seed, replay.building + 1)));
+        // One seed being a prefix of another mustn't match
+        assert!(!name.starts_with(&Replay::file_prefix(0xDEADBEE, replay.building)));
+    }
+
+    #[test]
+    fn test_bad_files() {
+        let bytes = replay().to_bytes();
+        assert!(matches!(
+            Replay::of_bytes(b"nope"),
+            Err(ReplayError::NotAReplay)
+        ));
+        assert!(matches!(
+            Replay::of_bytes(&bytes[..bytes.len() - 1]),
+            Err(ReplayError::Truncated)
+        ));
+        let mut skyscraper = bytes.clone();
+        skyscraper[32..36].copy_from_slice(&4_000_000_000u32.to_le_bytes());
+        assert!(matches!(
+            Replay::of_bytes(&skyscraper),
+            Err(ReplayError::NotAReplay)
+        ));
+        let mut future = bytes.clone();
+        future[4..6].copy_from_slice(&(REPLAY_VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            Replay::of_bytes(&future),
+            Err(ReplayError::UnsupportedVersion(_))
+        ));
+    }
+}
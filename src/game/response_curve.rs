@@ -0,0 +1,163 @@
+use bevy::prelude::*;
+
+/// Number of control points in [ResponseCurve::Custom], evenly spaced over the input range
+pub const CUSTOM_CURVE_POINTS: usize = 5;
+
+/// Shape of the mapping from dial deflection to lift speed, both normalised to [0, 1]
+#[derive(Debug, Clone, PartialEq, Reflect)]
+pub enum ResponseCurve {
+    Linear,
+    Quadratic,
+    Cubic,
+    /// Catmull-Rom spline through [CUSTOM_CURVE_POINTS] outputs at evenly spaced inputs
+    Custom([f32; CUSTOM_CURVE_POINTS]),
+}
+
+impl ResponseCurve {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Linear => "Linear",
+            Self::Quadratic => "Quadratic",
+            Self::Cubic => "Cubic",
+            Self::Custom(_) => "Custom",
+        }
+    }
+
+    /// A custom curve starting as a copy of this one
+    pub fn to_custom(&self) -> Self {
+        match self {
+            Self::Custom(_) => self.clone(),
+            _ => {
+                let mut points = [0.0; CUSTOM_CURVE_POINTS];
+                for (i, point) in points.iter_mut().enumerate() {
+                    *point = self.apply(i as f32 / (CUSTOM_CURVE_POINTS - 1) as f32);
+                }
+                Self::Custom(points)
+            }
+        }
+    }
+
+    pub fn apply(&self, input: f32) -> f32 {
+        let input = input.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => input,
+            Self::Quadratic => input * input,
+            Self::Cubic => input * input * input,
+            Self::Custom(points) => catmull_rom(points, input).clamp(0.0, 1.0),
+        }
+    }
+}
+
+fn catmull_rom(points: &[f32; CUSTOM_CURVE_POINTS], input: f32) -> f32 {
+    let segments = (CUSTOM_CURVE_POINTS - 1) as f32;
+    let position = input * segments;
+    let i = (position.floor() as usize).min(CUSTOM_CURVE_POINTS - 2);
+    let t = position - i as f32;
+    // End points are repeated so the curve still passes through them
+    let p0 = points[i.saturating_sub(1)];
+    let p1 = points[i];
+    let p2 = points[i + 1];
+    let p3 = points[(i + 2).min(CUSTOM_CURVE_POINTS - 1)];
+    0.5 * ((2.0 * p1)
+        + (-p0 + p2) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t * t
+        + (-p0 + 3.0 * p1 - 3.0 * p2 + p3) * t * t * t)
+}
+
+/// How the speed dial responds to the player, configured in settings
+#[derive(Debug, Clone, Reflect)]
+pub struct DialResponse {
+    pub curve: ResponseCurve,
+    /// Fraction of the dial's travel either side of centre which gives no speed
+    pub deadzone: f32,
+    /// Speed at full deflection of the dial
+    pub max_speed: f32,
+    /// Radians of dial rotation per unit of mouse movement
+    pub mouse_sensitivity: f32,
+    /// Fraction of the way the dial catches up with where it's been pushed in a 60fps frame, see
+    /// [DialResponse::catch_up]
+    pub responsiveness: f32,
+}
+
+impl Default for DialResponse {
+    /// Matches the original hardcoded squared response
+    fn default() -> Self {
+        Self {
+            curve: ResponseCurve::Quadratic,
+            deadzone: 0.0,
+            max_speed: 84.5,
+            mouse_sensitivity: 0.005,
+            responsiveness: 0.2,
+        }
+    }
+}
+
+impl DialResponse {
+    /// Map a dial deflection in [-1, 1] to a signed speed, positive deflection being upwards
+    pub fn speed(&self, deflection: f32) -> f32 {
+        let magnitude = deflection.abs().min(1.0);
+        if magnitude <= self.deadzone {
+            return 0.0;
+        }
+        let input = (magnitude - self.deadzone) / (1.0 - self.deadzone);
+        self.curve.apply(input) * self.max_speed * deflection.signum()
+    }
+
+    /// Fraction of the way to catch up over [delta_seconds], so the dial feels the same at any
+    /// frame rate
+    pub fn catch_up(&self, delta_seconds: f32) -> f32 {
+        1.0 - (1.0 - self.responsiveness).powf(delta_seconds * 60.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_curves_hit_end_points() {
+        let curves = [
+            ResponseCurve::Linear,
+            ResponseCurve::Quadratic,
+            ResponseCurve::Cubic,
+            ResponseCurve::Cubic.to_custom(),
+        ];
+        for curve in curves.iter() {
+            assert_eq!(curve.apply(0.0), 0.0, "{}", curve.name());
+            assert!((curve.apply(1.0) - 1.0).abs() < 1e-6, "{}", curve.name());
+        }
+        assert_eq!(ResponseCurve::Quadratic.apply(0.5), 0.25);
+    }
+
+    #[test]
+    fn test_custom_curve_passes_through_points() {
+        let points = [0.0, 0.5, 0.6, 0.7, 1.0];
+        let curve = ResponseCurve::Custom(points);
+        for (i, point) in points.iter().enumerate() {
+            let input = i as f32 / (CUSTOM_CURVE_POINTS - 1) as f32;
+            assert!((curve.apply(input) - point).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_deadzone_and_sign() {
+        let response = DialResponse {
+            curve: ResponseCurve::Linear,
+            deadzone: 0.2,
+            max_speed: 10.0,
+            ..default()
+        };
+        assert_eq!(response.speed(0.1), 0.0);
+        assert_eq!(response.speed(-0.2), 0.0);
+        assert!((response.speed(0.6) - 5.0).abs() < 1e-6);
+        assert!((response.speed(-1.0) + 10.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_catch_up_is_frame_rate_independent() {
+        let response = DialResponse::default();
+        assert!((response.catch_up(1.0 / 60.0) - response.responsiveness).abs() < 1e-6);
+        let left_after_two_frames = (1.0 - response.catch_up(1.0 / 120.0)).powi(2);
+        assert!((1.0 - left_after_two_frames - response.responsiveness).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,19 @@
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+
+/// Seeds everything random about a day, so the same seed (and the same inputs) always plays
+/// out the same day. Kept across [crate::GameState::RestartingDay]
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub struct DaySeed(pub u64);
+
+impl Default for DaySeed {
+    fn default() -> Self {
+        Self(rand::random())
+    }
+}
+
+/// The day's source of randomness, seeded from [DaySeed] when the floors are built. Anything
+/// affecting the outcome of the day must draw from this, in [FixedUpdate], rather than
+/// `thread_rng`
+#[derive(Resource, Debug, Deref, DerefMut)]
+pub struct GameRng(pub StdRng);
@@ -0,0 +1,275 @@
+use crate::game::floors::{floor_num_pretty_str, FloorNum};
+use crate::game::game_clock::TimeOfDay;
+use crate::game::spawn_simulation::{ResolvedFloorConfig, SinkOrSource};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// How much busier every floor gets while being evacuated
+const EVACUATION_STRENGTH_FACTOR: usize = 3;
+
+/// A one-off happening which perturbs the normal spawn behaviour for a window of the day
+#[derive(Clone, Debug)]
+pub struct ScheduledEvent {
+    pub name: String,
+    pub start: TimeOfDay,
+    pub end: TimeOfDay,
+    pub kind: EventKind,
+}
+
+#[derive(Clone, Debug)]
+pub enum EventKind {
+    /// Replace a floor's usual behaviour, e.g. a conference pulling people in
+    Override {
+        floor: FloorNum,
+        sink_or_source: SinkOrSource,
+        strength: usize,
+    },
+    /// Everyone heads for the one floor, e.g. a fire drill down to the lowest served floor
+    Evacuate { to: FloorNum },
+    /// Floor neither spawns nor accepts people, e.g. maintenance
+    Closed { floor: FloorNum },
+}
+
+impl ScheduledEvent {
+    pub fn new(name: &str, start: TimeOfDay, end: TimeOfDay, kind: EventKind) -> Self {
+        Self {
+            name: name.to_string(),
+            start,
+            end,
+            kind,
+        }
+    }
+
+    pub fn is_active(&self, time: &TimeOfDay) -> bool {
+        self.start <= *time && *time < self.end
+    }
+
+    fn apply(&self, mut floors: Vec<ResolvedFloorConfig>) -> Vec<ResolvedFloorConfig> {
+        match &self.kind {
+            EventKind::Override {
+                floor,
+                sink_or_source,
+                strength,
+            } => {
+                for config in floors.iter_mut().filter(|c| c.floor_num == *floor) {
+                    config.sink_or_source = *sink_or_source;
+                    config.strength = *strength;
+                }
+            }
+            EventKind::Evacuate { to } => {
+                for config in floors.iter_mut() {
+                    if config.floor_num == *to {
+                        config.sink_or_source = SinkOrSource::Sink;
+                    } else {
+                        config.sink_or_source = SinkOrSource::Source;
+                        config.strength *= EVACUATION_STRENGTH_FACTOR;
+                    }
+                }
+            }
+            EventKind::Closed { floor } => floors.retain(|c| c.floor_num != *floor),
+        }
+        floors
+    }
+}
+
+/// The events planned for a day, applied on top of the resolved floor configs while active
+#[derive(Clone, Debug, Default)]
+pub struct EventSchedule {
+    events: Vec<ScheduledEvent>,
+}
+
+impl EventSchedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, event: ScheduledEvent) -> Self {
+        self.add(event);
+        self
+    }
+
+    pub fn add(&mut self, event: ScheduledEvent) {
+        self.events.push(event);
+        self.events.sort_by_key(|event| event.start);
+    }
+
+    pub fn events(&self) -> &[ScheduledEvent] {
+        &self.events
+    }
+
+    /// Indices of the events running at [time], used to notice when re-resolving is needed
+    pub fn active_at(&self, time: &TimeOfDay) -> Vec<usize> {
+        self.events
+            .iter()
+            .enumerate()
+            .filter(|(_, event)| event.is_active(time))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Events starting after [time], but no more than [within_minutes] after
+    pub fn upcoming(
+        &self,
+        time: &TimeOfDay,
+        within_minutes: u16,
+    ) -> impl Iterator<Item = &ScheduledEvent> {
+        let now = time.minutes_since_midnight();
+        self.events.iter().filter(move |event| {
+            let start = event.start.minutes_since_midnight();
+            start > now && start - now <= within_minutes
+        })
+    }
+
+    pub fn apply(
+        &self,
+        active: &[usize],
+        mut floors: Vec<ResolvedFloorConfig>,
+    ) -> Vec<ResolvedFloorConfig> {
+        for i in active {
+            floors = self.events[*i].apply(floors);
+        }
+        floors
+    }
+
+    /// Make up a handful of events so no two days are quite the same
+    pub fn generate<R: Rng>(floors: &[FloorNum], rng: &mut R) -> Self {
+        let mut schedule = Self::new();
+        let (Some(lowest), Some(highest)) = (floors.iter().min(), floors.iter().max()) else {
+            return schedule;
+        };
+
+        if rng.gen_bool(0.6) {
+            let floor = *floors.choose(rng).unwrap();
+            let start = rng.gen_range(9..15);
+            schedule.add(ScheduledEvent::new(
+                "Conference",
+                TimeOfDay::new(start, 0),
+                TimeOfDay::new(start + 2, 0),
+                EventKind::Override {
+                    floor,
+                    sink_or_source: SinkOrSource::Sink,
+                    strength: 10,
+                },
+            ));
+        }
+        if rng.gen_bool(0.5) {
+            let floor = *floors.choose(rng).unwrap();
+            schedule.add(ScheduledEvent::new(
+                "Lunch Rush",
+                TimeOfDay::new(12, 0),
+                TimeOfDay::new(13, 30),
+                EventKind::Override {
+                    floor,
+                    sink_or_source: SinkOrSource::Both {
+                        sink_weight: 3,
+                        source_weight: 1,
+                    },
+                    strength: 8,
+                },
+            ));
+        }
+        if rng.gen_bool(0.3) {
+            // Don't close the floors at either end, people need to come and go somehow
+            let middle_floors: Vec<FloorNum> = floors
+                .iter()
+                .filter(|floor| *floor != lowest && *floor != highest)
+                .cloned()
+                .collect();
+            if let Some(floor) = middle_floors.choose(rng) {
+                let start = rng.gen_range(7..18);
+                schedule.add(ScheduledEvent::new(
+                    "Maintenance",
+                    TimeOfDay::new(start, 0),
+                    TimeOfDay::new(start + 3, 0),
+                    EventKind::Closed { floor: *floor },
+                ));
+            }
+        }
+        if rng.gen_bool(0.2) {
+            // G is the building's foundation, with no vestibule, so the drill empties every floor
+            // to the lowest one people can leave from
+            let start = rng.gen_range(10..17);
+            schedule.add(ScheduledEvent::new(
+                "Fire Drill",
+                TimeOfDay::new(start, 0),
+                TimeOfDay::new(start, 45),
+                EventKind::Evacuate { to: *lowest },
+            ));
+        }
+        schedule
+    }
+}
+
+impl EventKind {
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Override {
+                floor,
+                sink_or_source,
+                ..
+            } => {
+                let floor = floor_num_pretty_str(floor.0);
+                match sink_or_source {
+                    SinkOrSource::Sink => format!("Crowds heading to {}", floor),
+                    SinkOrSource::Source => format!("Crowds leaving {}", floor),
+                    _ => format!("Busy at {}", floor),
+                }
+            }
+            Self::Evacuate { to } => {
+                format!("Everyone out, down to {}", floor_num_pretty_str(to.0))
+            }
+            Self::Closed { floor } => format!("{} closed", floor_num_pretty_str(floor.0)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolved(floor_num: i32, sink_or_source: SinkOrSource) -> ResolvedFloorConfig {
+        ResolvedFloorConfig {
+            sink_or_source,
+            strength: 2,
+            floor_num: FloorNum(floor_num),
+        }
+    }
+
+    #[test]
+    fn test_apply_active_events() {
+        let schedule = EventSchedule::new()
+            .with(ScheduledEvent::new(
+                "Drill",
+                TimeOfDay::new(10, 0),
+                TimeOfDay::new(10, 30),
+                EventKind::Evacuate { to: FloorNum(1) },
+            ))
+            .with(ScheduledEvent::new(
+                "Maintenance",
+                TimeOfDay::new(9, 0),
+                TimeOfDay::new(12, 0),
+                EventKind::Closed { floor: FloorNum(2) },
+            ));
+        assert!(schedule.active_at(&TimeOfDay::new(8, 59)).is_empty());
+        let active = schedule.active_at(&TimeOfDay::new(10, 15));
+        assert_eq!(active.len(), 2);
+
+        let floors = vec![
+            resolved(1, SinkOrSource::Source),
+            resolved(2, SinkOrSource::Sink),
+            resolved(3, SinkOrSource::Sink),
+        ];
+        let applied = schedule.apply(&active, floors);
+        let summary: Vec<(FloorNum, SinkOrSource, usize)> = applied
+            .iter()
+            .map(|c| (c.floor_num, c.sink_or_source, c.strength))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (FloorNum(1), SinkOrSource::Sink, 2),
+                (FloorNum(3), SinkOrSource::Source, 6),
+            ]
+        );
+    }
+}
@@ -1,10 +1,12 @@
 use crate::game::floors::FloorNum;
 use crate::game::game_clock::{GameTime, TimeOfDay};
-use bevy::prelude::{Component, Deref, Resource};
+use crate::game::spawn_simulation::{EventSchedule, ScheduledEvent};
+use bevy::prelude::{debug, Component, Deref, Resource};
 use bevy::time::Time;
 use rand::seq::SliceRandom;
 use rand::Rng;
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::fmt::{Debug, Formatter};
 use std::ops::Range;
@@ -30,11 +32,17 @@ pub struct HourOfDay(u8);
 pub enum SinkOrSource {
     Sink,
     Source,
-    // TODO: Re-add this wildcard later
-    //Random,
+    /// Wildcard, flips a coin for [Sink] or [Source] every time the hour is resolved
+    Random,
+    /// Floor acts as a sink and a source at the same time (e.g. lobbies, restaurants), with its
+    /// strength split between the two by the relative weights
+    Both {
+        sink_weight: usize,
+        source_weight: usize,
+    },
 }
 
-#[derive(Clone, Debug, Component)]
+#[derive(Clone, Debug, PartialEq, Component)]
 pub struct RawFloorConfig {
     sink_or_source: [SinkOrSource; 24],
     strength: [usize; 24],
@@ -63,14 +71,50 @@ fn resolve_and_validate_range<T: Copy>(
 
 #[derive(Clone, Debug)]
 pub struct ResolvedFloorConfig {
-    sink_or_source: SinkOrSource,
-    strength: usize,
-    floor_num: FloorNum,
+    pub(super) sink_or_source: SinkOrSource,
+    pub(super) strength: usize,
+    pub(super) floor_num: FloorNum,
+}
+
+impl ResolvedFloorConfig {
+    fn sink_strength(&self) -> f32 {
+        match self.sink_or_source {
+            SinkOrSource::Sink => self.strength as f32,
+            SinkOrSource::Both {
+                sink_weight,
+                source_weight,
+            } => split_strength(self.strength, sink_weight, source_weight),
+            // Random is always resolved away in [RawFloorConfig::resolve]
+            SinkOrSource::Source | SinkOrSource::Random => 0.0,
+        }
+    }
+
+    fn source_strength(&self) -> f32 {
+        match self.sink_or_source {
+            SinkOrSource::Source => self.strength as f32,
+            SinkOrSource::Both {
+                sink_weight,
+                source_weight,
+            } => split_strength(self.strength, source_weight, sink_weight),
+            SinkOrSource::Sink | SinkOrSource::Random => 0.0,
+        }
+    }
+}
+
+/// The share of [strength] given to [weight], out of [weight] + [other_weight]
+fn split_strength(strength: usize, weight: usize, other_weight: usize) -> f32 {
+    let total = weight + other_weight;
+    if total == 0 {
+        0.0
+    } else {
+        strength as f32 * weight as f32 / total as f32
+    }
 }
 
 impl HourOfDay {
     pub fn of_time_ofday(time_ofday: &TimeOfDay) -> Self {
-        Self(time_ofday.hour)
+        // The clock reads 24:00 once the day is over, which still belongs to the last hour
+        Self(time_ofday.hour.min(23))
     }
 }
 
@@ -102,8 +146,30 @@ impl RawFloorConfig {
             strength,
         })
     }
-    pub fn resolve(&self, hour_of_day: HourOfDay, floor_num: FloorNum) -> ResolvedFloorConfig {
-        let sink_or_source = self.sink_or_source[hour_of_day.0 as usize];
+
+    pub fn sink_or_source(&self) -> &[SinkOrSource; 24] {
+        &self.sink_or_source
+    }
+
+    pub fn strength(&self) -> &[usize; 24] {
+        &self.strength
+    }
+    pub fn resolve<R: Rng>(
+        &self,
+        hour_of_day: HourOfDay,
+        floor_num: FloorNum,
+        rng: &mut R,
+    ) -> ResolvedFloorConfig {
+        let sink_or_source = match self.sink_or_source[hour_of_day.0 as usize] {
+            SinkOrSource::Random => {
+                if rng.gen_bool(0.5) {
+                    SinkOrSource::Sink
+                } else {
+                    SinkOrSource::Source
+                }
+            }
+            other => other,
+        };
         let strength = self.strength[hour_of_day.0 as usize];
         ResolvedFloorConfig {
             sink_or_source,
@@ -113,51 +179,124 @@ impl RawFloorConfig {
     }
 }
 
+/// What a floor is doing right now, i.e. whether people are leaving it, heading to it, or both
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloorActivity {
+    Idle,
+    Sink,
+    Source,
+    Both,
+}
+
 #[derive(Debug, Resource)]
 pub struct FloorSpawnManager {
     floor_spawn_rates: FloorSpawnRates,
-    raw_floors: HashMap<FloorNum, RawFloorConfig>,
+    raw_floors: BTreeMap<FloorNum, RawFloorConfig>,
+    schedule: EventSchedule,
+    active_events: Vec<usize>,
+    /// Multiplies every floor's spawn rate, see [FloorSpawnManager::set_strength_scale]
+    strength_scale: f32,
 }
 
-fn resolve_all(
-    floors: &HashMap<FloorNum, RawFloorConfig>,
+fn resolve_all<R: Rng>(
+    floors: &BTreeMap<FloorNum, RawFloorConfig>,
     time_range: HourOfDay,
+    rng: &mut R,
 ) -> Vec<ResolvedFloorConfig> {
     floors
         .iter()
-        .map(|(floor_num, raw)| raw.resolve(time_range, *floor_num))
+        .map(|(floor_num, raw)| raw.resolve(time_range, *floor_num, rng))
         .collect()
 }
 
 impl FloorSpawnManager {
-    pub fn new(raw_floors: HashMap<FloorNum, RawFloorConfig>) -> Self {
-        let resolved = resolve_all(&raw_floors, HourOfDay(0));
+    pub fn new<R: Rng>(raw_floors: BTreeMap<FloorNum, RawFloorConfig>, rng: &mut R) -> Self {
+        let resolved = resolve_all(&raw_floors, HourOfDay(0), rng);
         let floor_spawn_rates = FloorSpawnRates::get_rates(resolved, HourOfDay(0));
         Self {
             floor_spawn_rates,
             raw_floors,
+            schedule: EventSchedule::new(),
+            active_events: vec![],
+            strength_scale: 1.0,
         }
     }
+
+    pub fn with_schedule(mut self, schedule: EventSchedule) -> Self {
+        self.schedule = schedule;
+        self
+    }
+
+    /// Scale how many people turn up, on top of the floor configs and events. 1.0 leaves them be
+    pub fn set_strength_scale(&mut self, strength_scale: f32) {
+        self.strength_scale = strength_scale;
+    }
+
+    pub fn schedule(&self) -> &EventSchedule {
+        &self.schedule
+    }
+
+    pub fn activity(&self, floor_num: FloorNum) -> FloorActivity {
+        self.floor_spawn_rates.activity(floor_num)
+    }
+
+    pub fn active_events(&self) -> impl Iterator<Item = &ScheduledEvent> {
+        self.active_events
+            .iter()
+            .map(|i| &self.schedule.events()[*i])
+    }
+
     pub fn tick<R: Rng>(
         &mut self,
         game_time: &GameTime,
         delta: Duration,
         rng: &mut R,
     ) -> Vec<(FloorNum, FloorNum)> {
-        let hour = HourOfDay::of_time_ofday(&game_time.to_game_time_of_day());
-        if hour != self.floor_spawn_rates.resolved_for_hour {
+        let time_of_day = game_time.to_game_time_of_day();
+        let hour = HourOfDay::of_time_ofday(&time_of_day);
+        let active_events = self.schedule.active_at(&time_of_day);
+        if hour != self.floor_spawn_rates.resolved_for_hour || active_events != self.active_events {
             // Re-resolve
-            println!("Re-Resolving, time range changed (to: {:?})", hour);
-            let resolved: Vec<ResolvedFloorConfig> = resolve_all(&self.raw_floors, hour);
+            debug!(
+                "Re-Resolving, time range or events changed (to: {:?}, {:?})",
+                hour, active_events
+            );
+            let resolved: Vec<ResolvedFloorConfig> = resolve_all(&self.raw_floors, hour, rng);
+            let resolved = self.schedule.apply(&active_events, resolved);
             self.floor_spawn_rates = FloorSpawnRates::get_rates(resolved, hour);
+            self.active_events = active_events;
         }
-        self.floor_spawn_rates.tick(game_time, delta, rng)
+        self.floor_spawn_rates
+            .tick(game_time, delta, self.strength_scale, rng)
     }
 }
 
+/// Each floor's spawn rate in people per game hour for every hour of the day, as the
+/// [FloorSpawnManager] would have them with no events on. [SinkOrSource::Random] is rolled with
+/// [rng], as it would be in game
+pub fn hourly_spawn_rates<R: Rng>(
+    raw_floors: &BTreeMap<FloorNum, RawFloorConfig>,
+    rng: &mut R,
+) -> BTreeMap<FloorNum, [f32; 24]> {
+    let mut hourly_rates: BTreeMap<FloorNum, [f32; 24]> = raw_floors
+        .keys()
+        .map(|floor_num| (*floor_num, [0.0; 24]))
+        .collect();
+    for hour in 0..24 {
+        let resolved = resolve_all(raw_floors, HourOfDay(hour), rng);
+        let rates = FloorSpawnRates::get_rates(resolved, HourOfDay(hour));
+        for (floor_num, rate) in rates.floors_with_rates.iter() {
+            if let Some(floor_rates) = hourly_rates.get_mut(floor_num) {
+                floor_rates[hour as usize] = rate.people_per_game_hour;
+            }
+        }
+    }
+    hourly_rates
+}
+
 #[derive(Debug)]
 pub struct FloorSpawnRates {
-    floors_with_rates: HashMap<FloorNum, SpawnRate>,
+    floors_with_rates: BTreeMap<FloorNum, SpawnRate>,
     sinks: Sinks,
     resolved_for_hour: HourOfDay,
 }
@@ -167,18 +306,24 @@ pub struct FloorSpawnRates {
 // The target is then resolved from sink floors
 impl FloorSpawnRates {
     pub fn get_rates(floors: Vec<ResolvedFloorConfig>, time_range: HourOfDay) -> Self {
-        let (mut sinks, mut sources): (Vec<_>, Vec<_>) =
-            floors
-                .into_iter()
-                .partition(|floor| match floor.sink_or_source {
-                    SinkOrSource::Sink => true,
-                    SinkOrSource::Source => false,
-                });
+        // Not a straight partition, as [SinkOrSource::Both] floors belong on both sides
+        let mut sinks: Vec<ResolvedFloorConfig> = floors
+            .iter()
+            .filter(|floor| floor.sink_strength() > 0.0)
+            .cloned()
+            .collect();
+        let sources: Vec<ResolvedFloorConfig> = floors
+            .into_iter()
+            .filter(|floor| floor.source_strength() > 0.0)
+            .collect();
         // Sort sinks so strongest is first
-        sinks.sort_by_key(|floor| floor.strength);
-        sinks.reverse();
-        let mut floors_with_rates = HashMap::new();
-        // Add as sinks with zero rates
+        sinks.sort_by(|a, b| {
+            b.sink_strength()
+                .partial_cmp(&a.sink_strength())
+                .unwrap_or(Ordering::Equal)
+        });
+        let mut floors_with_rates = BTreeMap::new();
+        // Add as sinks with zero rates, floors which are also sources get overwritten below
         for sink in sinks.iter() {
             floors_with_rates.insert(
                 sink.floor_num,
@@ -188,7 +333,11 @@ impl FloorSpawnRates {
             );
         }
         for source in sources {
-            let rate = source.strength as f32 / sinks.len() as f32;
+            let rate = if sinks.is_empty() {
+                0.0
+            } else {
+                source.source_strength() / sinks.len() as f32
+            };
             floors_with_rates.insert(
                 source.floor_num,
                 SpawnRate {
@@ -205,17 +354,32 @@ impl FloorSpawnRates {
         }
     }
 
+    pub fn activity(&self, floor_num: FloorNum) -> FloorActivity {
+        let is_sink = self.sinks.0.iter().any(|sink| sink.floor_num == floor_num);
+        let is_source = self
+            .floors_with_rates
+            .get(&floor_num)
+            .is_some_and(|rate| rate.people_per_game_hour > 0.0);
+        match (is_sink, is_source) {
+            (true, true) => FloorActivity::Both,
+            (true, false) => FloorActivity::Sink,
+            (false, true) => FloorActivity::Source,
+            (false, false) => FloorActivity::Idle,
+        }
+    }
+
     pub fn tick<R: Rng>(
         &self,
         game_time: &GameTime,
         delta: Duration,
+        strength_scale: f32,
         rng: &mut R,
     ) -> Vec<(FloorNum, FloorNum)> {
         let mut spawn_floors: Vec<FloorNum> = Vec::new();
         // chance a person spawns in this span, is humans/hour * factor_of_hours
         let delta_hrs = game_time.to_hrs_f32(&delta);
         for (floor, rate) in self.floors_with_rates.iter() {
-            let chance = rate.people_per_game_hour * delta_hrs;
+            let chance = rate.people_per_game_hour * strength_scale * delta_hrs;
             let roll: f32 = rng.gen();
             if roll < chance {
                 spawn_floors.push(*floor);
@@ -225,19 +389,22 @@ impl FloorSpawnRates {
         // Now decide destination, for any floors that will spawn
         let mut floor_and_dest = Vec::new();
         for spawn_floor in spawn_floors.iter() {
-            let sink = self
-                .sinks
-                .0
-                .choose_weighted(rng, |floor| {
-                    let dfloor = (floor.floor_num.0 - spawn_floor.0).abs();
-                    if dfloor > 1 {
-                        floor.strength as f64
-                    } else {
-                        0.5f64
-                    }
-                })
-                .unwrap();
-            floor_and_dest.push((*spawn_floor, sink.floor_num));
+            let sink = self.sinks.0.choose_weighted(rng, |floor| {
+                let dfloor = (floor.floor_num.0 - spawn_floor.0).abs();
+                if dfloor == 0 {
+                    // Floors which are both sink and source shouldn't send people to themselves
+                    0.0f64
+                } else if dfloor > 1 {
+                    floor.sink_strength() as f64
+                } else {
+                    0.5f64
+                }
+            });
+            // Can only fail if the spawn floor is the only sink, in which case there's nowhere
+            // to go
+            if let Ok(sink) = sink {
+                floor_and_dest.push((*spawn_floor, sink.floor_num));
+            }
         }
         floor_and_dest
     }
@@ -252,3 +419,94 @@ pub struct SpawnRate {
 // A vec of sink floors, sorted by strength order
 #[derive(Debug, Clone)]
 pub struct Sinks(pub Vec<ResolvedFloorConfig>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    fn resolved(
+        floor_num: i32,
+        sink_or_source: SinkOrSource,
+        strength: usize,
+    ) -> ResolvedFloorConfig {
+        ResolvedFloorConfig {
+            sink_or_source,
+            strength,
+            floor_num: FloorNum(floor_num),
+        }
+    }
+
+    #[test]
+    fn test_random_resolves_to_sink_or_source() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1337);
+        let raw =
+            RawFloorConfig::new(vec![(0..24, SinkOrSource::Random)], vec![(0..24, 1)]).unwrap();
+        let mut seen = vec![];
+        for _ in 0..20 {
+            let resolved = raw.resolve(HourOfDay(12), FloorNum(3), &mut rng);
+            assert_ne!(resolved.sink_or_source, SinkOrSource::Random);
+            seen.push(resolved.sink_or_source);
+        }
+        assert!(seen.contains(&SinkOrSource::Sink));
+        assert!(seen.contains(&SinkOrSource::Source));
+    }
+
+    #[test]
+    fn test_both_is_sink_and_source() {
+        let both = SinkOrSource::Both {
+            sink_weight: 3,
+            source_weight: 1,
+        };
+        let rates = FloorSpawnRates::get_rates(
+            vec![
+                resolved(0, both, 8),
+                resolved(1, SinkOrSource::Sink, 4),
+                resolved(2, SinkOrSource::Source, 2),
+            ],
+            HourOfDay(0),
+        );
+        let sink_floors: Vec<FloorNum> = rates.sinks.0.iter().map(|f| f.floor_num).collect();
+        assert_eq!(sink_floors, vec![FloorNum(0), FloorNum(1)]);
+        // 8 strength, quarter of it as a source, split across two sinks
+        assert_eq!(
+            rates.floors_with_rates[&FloorNum(0)].people_per_game_hour,
+            1.0
+        );
+        assert_eq!(
+            rates.floors_with_rates[&FloorNum(1)].people_per_game_hour,
+            0.0
+        );
+        assert_eq!(
+            rates.floors_with_rates[&FloorNum(2)].people_per_game_hour,
+            1.0
+        );
+        assert_eq!(rates.activity(FloorNum(0)), FloorActivity::Both);
+        assert_eq!(rates.activity(FloorNum(1)), FloorActivity::Sink);
+        assert_eq!(rates.activity(FloorNum(2)), FloorActivity::Source);
+        assert_eq!(rates.activity(FloorNum(3)), FloorActivity::Idle);
+    }
+
+    #[test]
+    fn test_hourly_spawn_rates() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1337);
+        let raw_floors = BTreeMap::from([
+            (
+                FloorNum(1),
+                RawFloorConfig::new(
+                    vec![(0..12, SinkOrSource::Source), (12..24, SinkOrSource::Sink)],
+                    vec![(0..24, 4)],
+                )
+                .unwrap(),
+            ),
+            (
+                FloorNum(2),
+                RawFloorConfig::new(vec![(0..24, SinkOrSource::Sink)], vec![(0..24, 2)]).unwrap(),
+            ),
+        ]);
+        let rates = hourly_spawn_rates(&raw_floors, &mut rng);
+        assert_eq!(rates[&FloorNum(1)][0], 4.0);
+        assert_eq!(rates[&FloorNum(1)][12], 0.0);
+        assert_eq!(rates[&FloorNum(2)], [0.0; 24]);
+    }
+}
@@ -1,7 +1,9 @@
+mod events;
 mod logic;
 mod oneshot_simulation;
 pub mod prefabs;
 
+pub use events::*;
 pub use logic::*;
 
 pub use oneshot_simulation::run_spawn_example as oneshot_simulation;
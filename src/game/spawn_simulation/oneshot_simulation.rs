@@ -1,6 +1,7 @@
 use crate::game;
 use crate::game::spawn_simulation::prefabs;
 use crate::game::spawn_simulation::{FloorSpawnManager, SinkOrSource};
+use crate::game::telemetry::{self, SessionEvent};
 use crate::game::{spawn_simulation, FloorNum};
 use rand::thread_rng;
 use std::fs::File;
@@ -51,13 +52,16 @@ pub fn run_spawn_example() {
         .enumerate()
         .map(|(i, raw)| (FloorNum(i as i32), raw.unwrap()))
         .collect();
-    let mut manager = FloorSpawnManager::new(floors);
     let mut rng = thread_rng();
+    let mut manager = FloorSpawnManager::new(floors, &mut rng);
     let output_filename = "spawn_output.csv";
     let output_file = File::create(output_filename).unwrap();
     let mut output_writer = BufWriter::new(output_file);
     output_writer
-        .write_all(game_clock.config().to_csv().as_bytes())
+        .write_all(telemetry::csv_preamble(game_clock.config()).as_bytes())
+        .unwrap();
+    output_writer
+        .write_all(telemetry::EVENT_CSV_HEADER.as_bytes())
         .unwrap();
     let tick_size = Duration::from_secs(1);
     let mut num_ticks = 0;
@@ -65,8 +69,8 @@ pub fn run_spawn_example() {
         num_ticks += 1;
         let spawns = manager.tick(&game_clock, tick_size, &mut rng);
         for (from, to_) in spawns {
-            let line = format!("{},{},{}\n", game_clock.to_string_secs(), from.0, to_.0);
-            output_writer.write_all(line.as_bytes()).unwrap()
+            let event = SessionEvent::spawn(game_clock.elapsed_secs(), from.0, to_.0);
+            output_writer.write_all(event.to_csv().as_bytes()).unwrap()
         }
         if game_clock.tick(tick_size) {
             break;
@@ -12,6 +12,48 @@ pub fn ground_floor_source() -> Sr {
     ]
 }
 
+/// Like [ground_floor_source] but people come and go all day, just more heavily one way
+pub fn lobby() -> Sr {
+    let arriving = SinkOrSource::Both {
+        sink_weight: 1,
+        source_weight: 3,
+    };
+    let leaving = SinkOrSource::Both {
+        sink_weight: 3,
+        source_weight: 1,
+    };
+    vec![
+        (0..10, arriving),
+        (10..16, leaving),
+        (16..17, arriving),
+        (17..24, leaving),
+    ]
+}
+
+/// Fills up at meal times, but there's always someone heading back out
+pub fn restaurant() -> Sr {
+    let meal_time = SinkOrSource::Both {
+        sink_weight: 3,
+        source_weight: 1,
+    };
+    let between_meals = SinkOrSource::Both {
+        sink_weight: 1,
+        source_weight: 2,
+    };
+    vec![
+        (0..11, SinkOrSource::Source),
+        (11..14, meal_time),
+        (14..18, between_meals),
+        (18..21, meal_time),
+        (21..24, SinkOrSource::Source),
+    ]
+}
+
+/// No fixed behaviour, re-rolled every hour
+pub fn wildcard() -> Sr {
+    always(SinkOrSource::Random)
+}
+
 pub fn normal_hours(out_of_hours: usize, in_hours: usize) -> Vec<(Range<u8>, usize)> {
     vec![
         (0..9, out_of_hours),
@@ -53,11 +95,27 @@ pub fn alternating_sink(mode: bool) -> Sr {
     ]
 }
 
-pub fn generate_config_of_floor_num(floor_num: i32) -> RawFloorConfig {
-    let (sink_or_source, strength) = match floor_num {
+/// Row 0 of the building is its foundation, with no vestibule, so the lobby goes on the floor
+/// above it
+pub const LOBBY_FLOOR_NUM: i32 = 1;
+/// Part way up, so most buildings have a floor which is both a sink and a source
+pub const RESTAURANT_FLOOR_NUM: i32 = LOBBY_FLOOR_NUM + 5;
+
+pub fn lobby_config() -> RawFloorConfig {
+    RawFloorConfig::new(lobby(), normal_hours(2, 15)).unwrap()
+}
+
+pub fn restaurant_config() -> RawFloorConfig {
+    RawFloorConfig::new(restaurant(), normal_hours(1, 4)).unwrap()
+}
+
+/// A regular floor, varying with how far above [LOBBY_FLOOR_NUM] it is, with a wildcard floor
+/// part way up. The lobby and restaurant have their own configs
+pub fn regular_config(floor_num: i32) -> RawFloorConfig {
+    let (sink_or_source, strength) = match floor_num - LOBBY_FLOOR_NUM {
         ..=-1 => (afternoon_sink(), normal_hours(1, 2)),
-        0 => (ground_floor_source(), normal_hours(2, 15)),
-        1..=12 => (alternating_sink(floor_num % 2 == 0), normal_hours(2, 3)),
+        8 => (wildcard(), normal_hours(1, 2)),
+        0..=12 => (alternating_sink(floor_num % 2 == 0), normal_hours(2, 3)),
         13.. => (afternoon_sink(), normal_hours(1, 2)),
     };
     RawFloorConfig::new(sink_or_source, strength).unwrap()
@@ -1,6 +1,8 @@
 use crate::camera::OverlayCamera;
 use crate::core::{InScreenSpaceLocation, ScreenSpaceAnchor, With2DScale};
-use crate::{GameState, InputAction};
+use crate::game::game::{DayScoped, DayScopedResourceExt};
+use crate::settings::Settings;
+use crate::{GameState, InputAction, PauseState};
 use bevy::asset::AssetLoader;
 use bevy::prelude::*;
 use bevy::render::view::RenderLayers;
@@ -12,20 +14,16 @@ const DIAL_Z: f32 = 100.0;
 const HANDLE_Z: f32 = 101.0;
 const ROTATION_MAX: f32 = 1.3;
 
-// TODO: This should be user configurable
-/// Factor by which mouse movement is turned into rotation radians
-const MOUSE_MOVE_ROTATION_FACTOR: f32 = 0.005;
-
-// TODO: Consider this factor being configurable and maybe non-linear?
-/// Factor by which target velocity is determined by multiplying with rotation of handle
-const TARGET_VELOCITY_FACTOR: f32 = -50.0;
+/// Rotation in radians per second applied while Up/Down are held
+const KEY_ROTATION_RATE: f32 = 1.5;
 
 const SCALE: f32 = 2.0;
 
 pub struct SpeedSelectorPlugin;
 
+/// Gizmos drawn in screen space, on the overlay
 #[derive(Default, Reflect, GizmoConfigGroup)]
-struct OverlayGizmos {}
+pub struct OverlayGizmos {}
 
 impl Plugin for SpeedSelectorPlugin {
     fn build(&self, app: &mut App) {
@@ -38,15 +36,17 @@ impl Plugin for SpeedSelectorPlugin {
             (
                 update_selector,
                 handle_selector_input,
+                handle_selector_touch,
                 cursor_position_system,
-                mouse_selection_rect_debug_gizmo,
                 position_cursor_selection_rect_system,
             )
-                .run_if(in_state(GameState::PlayingDay)),
+                .run_if(in_state(GameState::PlayingDay))
+                .run_if(in_state(PauseState::Running)),
         )
         .init_gizmo_group::<OverlayGizmos>()
-        .insert_resource(TargetVelocity(0.0))
-        .insert_resource(SelectionEnabled(false))
+        .init_day_resource::<TargetVelocity>()
+        .init_day_resource::<SelectionEnabled>()
+        .init_day_resource::<DialTouch>()
         .register_type::<Rotation>()
         .register_type::<SelectionEnabled>()
         .register_type::<MouseSelectionRect>();
@@ -71,11 +71,12 @@ fn spawn_selector(mut commands: Commands, asset_server: Res<AssetServer>) {
             ..default()
         })
         .insert(MouseSelectionRect::new(
-            Vec2::new(190.0, 370.0),
+            Vec2::new(95.0, 185.0),
             Rect::new(0.0, 0.0, 0.0, 0.0),
         ))
         .insert(Name::from("Speed Dial"))
         .insert(SpeedDial)
+        .insert(DayScoped)
         .insert(render_layers.clone())
         .insert(With2DScale::new(2.0))
         .insert(InScreenSpaceLocation::new(ScreenSpaceAnchor::Right, 80.0))
@@ -97,51 +98,38 @@ fn spawn_selector(mut commands: Commands, asset_server: Res<AssetServer>) {
 fn update_selector(
     mut query: Query<(&mut Rotation, &mut Transform), (With<SpeedHandle>)>,
     mut target_velocity: ResMut<TargetVelocity>,
+    settings: Res<Settings>,
+    time: Res<Time>,
 ) {
     for (mut rotation, mut transform) in query.iter_mut() {
-        let diff = rotation.update();
+        let diff = rotation.update(settings.dial.catch_up(time.delta_seconds()));
         transform.rotate_around(Vec3::new(23.0, 0.0, 0.0), Quat::from_rotation_z(diff));
-        target_velocity.0 = (rotation.actual.abs() * rotation.actual.abs())
-            * TARGET_VELOCITY_FACTOR
-            * rotation.actual.signum();
-    }
-}
-
-fn mouse_selection_rect_debug_gizmo(
-    selector_query: Query<(&MouseSelectionRect), With<SpeedDial>>,
-    mut gizmos: Gizmos<OverlayGizmos>,
-) {
-    for (selector_rect) in selector_query.iter() {
-        gizmos.rect_2d(
-            selector_rect.world_rect.center(),
-            0.0,
-            selector_rect.world_rect.half_size(),
-            Color::RED,
-        );
+        target_velocity.0 = settings.dial.speed(rotation.deflection());
     }
 }
 
+/// Where the dial can be grabbed, more generous than the dial itself
 #[derive(Debug, Component, Reflect)]
-struct MouseSelectionRect {
+pub(super) struct MouseSelectionRect {
+    /// In the dial's unscaled pixels, see [MouseSelectionRect::update]
     size: Vec2,
-    world_rect: Rect,
+    pub(super) world_rect: Rect,
 }
 impl MouseSelectionRect {
     fn new(size: Vec2, world_rect: Rect) -> Self {
         Self { size, world_rect }
     }
 
-    fn set_middle(&mut self, middle: Vec2) {
-        self.world_rect = Rect::new(
-            middle.x - self.size.x / 2.0,
-            middle.y - self.size.y / 2.0,
-            middle.x + self.size.x / 2.0,
-            middle.y + self.size.y / 2.0,
+    /// Follow the dial's position and scale, so the rect stays over it however big the ui is
+    fn update(&mut self, transform: &Transform) {
+        self.world_rect = Rect::from_center_size(
+            transform.translation.truncate(),
+            self.size * transform.scale.truncate(),
         );
     }
 }
-#[derive(Debug, Resource, Reflect)]
-struct SelectionEnabled(bool);
+#[derive(Debug, Default, Resource, Reflect)]
+pub struct SelectionEnabled(pub bool);
 
 fn cursor_position_system(
     mut window_query: Query<&mut Window, With<PrimaryWindow>>,
@@ -173,7 +161,7 @@ fn position_cursor_selection_rect_system(
     >,
 ) {
     for (transform, mut selector_rect) in selector_query.iter_mut() {
-        selector_rect.set_middle(transform.translation.truncate());
+        selector_rect.update(transform);
     }
 }
 
@@ -181,7 +169,10 @@ fn handle_selector_input(
     inputs: Query<&ActionState<InputAction>>,
     mut handle_query: Query<(&mut Rotation), With<SpeedHandle>>,
     mut mouse_held: Local<bool>,
+    mut throttle_engaged: Local<bool>,
     selection_enabled: Res<SelectionEnabled>,
+    time: Res<Time>,
+    settings: Res<Settings>,
 ) {
     let inputs = inputs.single();
     let mouse = inputs.value(&InputAction::MouseMove);
@@ -193,19 +184,89 @@ fn handle_selector_input(
     }
 
     if mouse != 0.0 && *mouse_held {
-        let angle_change = mouse * MOUSE_MOVE_ROTATION_FACTOR;
+        let angle_change = mouse * settings.dial.mouse_sensitivity;
         for mut rotation in handle_query.iter_mut() {
             let target = rotation.target + angle_change;
             rotation.set(target)
         }
     }
+
+    // Up is negative rotation, see [Rotation::deflection]
+    let mut key_direction = 0.0;
+    if inputs.pressed(&InputAction::Up) {
+        key_direction -= 1.0;
+    }
+    if inputs.pressed(&InputAction::Down) {
+        key_direction += 1.0;
+    }
+    if key_direction != 0.0 {
+        let angle_change = key_direction * KEY_ROTATION_RATE * time.delta_seconds();
+        for mut rotation in handle_query.iter_mut() {
+            let target = rotation.target + angle_change;
+            rotation.set(target)
+        }
+    }
+
+    // The stick maps directly onto the dial, but only takes over while it's being pushed
+    // (outside the deadzone) so it doesn't fight the other inputs when at rest
+    let throttle = inputs.value(&InputAction::Throttle);
+    if throttle != 0.0 {
+        *throttle_engaged = true;
+        for mut rotation in handle_query.iter_mut() {
+            rotation.set(-throttle * ROTATION_MAX)
+        }
+    } else if *throttle_engaged {
+        *throttle_engaged = false;
+        for mut rotation in handle_query.iter_mut() {
+            rotation.set(0.0)
+        }
+    }
+}
+
+/// The touch which last started on the dial, kept after it's released so other touch
+/// systems can tell a tap on the dial apart from a tap on the world behind it
+#[derive(Debug, Default, Resource)]
+pub struct DialTouch(pub Option<u64>);
+
+fn handle_selector_touch(
+    touches: Res<Touches>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<OverlayCamera>>,
+    selector_query: Query<(&MouseSelectionRect), With<SpeedDial>>,
+    mut handle_query: Query<(&mut Rotation), With<SpeedHandle>>,
+    mut dial_touch: ResMut<DialTouch>,
+    settings: Res<Settings>,
+) {
+    let (camera, camera_transform) = camera_query.single();
+    let selector_rect = selector_query.single();
+
+    for touch in touches.iter_just_pressed() {
+        let on_dial = camera
+            .viewport_to_world(camera_transform, touch.position())
+            .map(|ray| selector_rect.world_rect.contains(ray.origin.truncate()))
+            .unwrap_or(false);
+        dial_touch.0 = on_dial.then_some(touch.id());
+    }
+
+    // Leave two finger gestures to the camera
+    if touches.iter().count() > 1 {
+        return;
+    }
+    if let Some(touch) = dial_touch.0.and_then(|id| touches.get_pressed(id)) {
+        let angle_change = touch.delta().y * settings.dial.mouse_sensitivity;
+        if angle_change != 0.0 {
+            for mut rotation in handle_query.iter_mut() {
+                let target = rotation.target + angle_change;
+                rotation.set(target)
+            }
+        }
+    }
 }
 
 #[derive(Clone, Debug, Default, Component)]
 struct SpeedHandle;
 
 #[derive(Clone, Debug, Default, Component)]
-struct SpeedDial;
+pub(super) struct SpeedDial;
 
 #[derive(Clone, Debug, Component, Reflect)]
 struct Rotation {
@@ -218,12 +279,18 @@ impl Rotation {
         self.target = f32::clamp(target, -ROTATION_MAX, ROTATION_MAX);
     }
 
-    fn update(&mut self) -> f32 {
-        let new = crate::helpers::lerp(self.actual, self.target, 0.2);
+    fn update(&mut self, lerp_factor: f32) -> f32 {
+        let new = crate::helpers::lerp(self.actual, self.target, lerp_factor);
         let diff = new - self.actual;
         self.actual = new;
         return diff;
     }
+
+    /// Normalised position of the handle, -1 being fully down and 1 fully up. The handle
+    /// rotates clockwise (negative) to go up
+    fn deflection(&self) -> f32 {
+        -self.actual / ROTATION_MAX
+    }
 }
 
 impl Default for Rotation {
@@ -235,5 +302,5 @@ impl Default for Rotation {
     }
 }
 
-#[derive(Clone, Debug, Deref, DerefMut, Resource)]
+#[derive(Clone, Debug, Default, Deref, DerefMut, Resource)]
 pub struct TargetVelocity(pub f32);
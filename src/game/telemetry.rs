@@ -0,0 +1,335 @@
+//! Session logs of what happened during a day, written out as CSV for offline analysis.
+//!
+//! The event schema here is shared with the spawn simulation (`lift_simulation`), so its
+//! `spawn_output.csv` can be analysed with the same tools as a real day's `events.csv`.
+
+use crate::game::game::{
+    AccelerationLog, DayScopedResourceExt, JerkLog, ObservedVelocityLog, VelocityLog,
+};
+use crate::game::game_clock::GameTimeConfig;
+use crate::game::ui::GameCentralInfo;
+use crate::history_store::HistoryStore;
+use crate::{GameState, InputAction};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use leafwing_input_manager::action_state::ActionState;
+use std::collections::VecDeque;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SESSIONS_DIR: &str = "sessions";
+
+pub const EVENT_CSV_HEADER: &str = "time_secs,event,floor,destination_floor,comfort,tip\n";
+
+pub struct TelemetryPlugin;
+
+impl Plugin for TelemetryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            export_session_system.run_if(in_state(GameState::PlayingDay)),
+        )
+        .init_day_resource::<SessionLog>()
+        .add_event::<DayEndedEvent>();
+    }
+}
+
+/// The day's clock has run out
+#[derive(Event, Debug, Clone)]
+pub struct DayEndedEvent {
+    pub day: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SessionEventKind {
+    /// A human appeared on [SessionEvent::floor], wanting to go to the destination floor
+    Spawn,
+    /// The lift collected a human from [SessionEvent::floor]
+    Pickup,
+    /// The lift dropped a human at [SessionEvent::floor], with their comfort and tip
+    Delivery,
+    /// A human ran out of patience, either riding to [SessionEvent::floor] or waiting on it
+    Abandon,
+}
+
+impl SessionEventKind {
+    fn as_str(&self) -> &str {
+        match self {
+            Self::Spawn => "spawn",
+            Self::Pickup => "pickup",
+            Self::Delivery => "delivery",
+            Self::Abandon => "abandon",
+        }
+    }
+}
+
+/// One row of the event CSV, see [EVENT_CSV_HEADER]
+#[derive(Debug, Clone)]
+pub struct SessionEvent {
+    /// Seconds since the start of the day
+    pub time_secs: f32,
+    pub kind: SessionEventKind,
+    pub floor: i32,
+    pub destination_floor: Option<i32>,
+    pub comfort: Option<f32>,
+    pub tip: Option<f32>,
+}
+
+impl SessionEvent {
+    pub fn spawn(time_secs: f32, floor: i32, destination_floor: i32) -> Self {
+        Self {
+            time_secs,
+            kind: SessionEventKind::Spawn,
+            floor,
+            destination_floor: Some(destination_floor),
+            comfort: None,
+            tip: None,
+        }
+    }
+
+    pub fn pickup(time_secs: f32, floor: i32, destination_floor: i32) -> Self {
+        Self {
+            time_secs,
+            kind: SessionEventKind::Pickup,
+            floor,
+            destination_floor: Some(destination_floor),
+            comfort: None,
+            tip: None,
+        }
+    }
+
+    pub fn delivery(time_secs: f32, floor: i32, comfort: f32, tip: f32) -> Self {
+        Self {
+            time_secs,
+            kind: SessionEventKind::Delivery,
+            floor,
+            destination_floor: None,
+            comfort: Some(comfort),
+            tip: Some(tip),
+        }
+    }
+
+    pub fn abandon(time_secs: f32, destination_floor: i32) -> Self {
+        Self {
+            time_secs,
+            kind: SessionEventKind::Abandon,
+            floor: destination_floor,
+            destination_floor: Some(destination_floor),
+            comfort: None,
+            tip: None,
+        }
+    }
+
+    /// A human waiting on [floor] gave up on the lift
+    pub fn abandon_waiting(time_secs: f32, floor: i32, destination_floor: i32) -> Self {
+        Self {
+            time_secs,
+            kind: SessionEventKind::Abandon,
+            floor,
+            destination_floor: Some(destination_floor),
+            comfort: None,
+            tip: None,
+        }
+    }
+
+    pub fn to_csv(&self) -> String {
+        fn optional<T: ToString>(value: Option<T>) -> String {
+            value.map_or(String::new(), |value| value.to_string())
+        }
+        format!(
+            "{},{},{},{},{},{}\n",
+            self.time_secs,
+            self.kind.as_str(),
+            self.floor,
+            optional(self.destination_floor),
+            optional(self.comfort),
+            optional(self.tip),
+        )
+    }
+}
+
+/// Metadata lines written at the top of an event CSV, prefixed with '#' so they can be skipped
+/// as comments
+pub fn csv_preamble(config: &GameTimeConfig) -> String {
+    format!("# time_per_day_secs={}\n", config.time_per_day().as_secs())
+}
+
+/// Everything that happened in the current day
+#[derive(Resource, Debug, Default)]
+pub struct SessionLog {
+    events: Vec<SessionEvent>,
+    deliveries: usize,
+    abandonments: usize,
+}
+
+impl SessionLog {
+    pub fn push(&mut self, event: SessionEvent) {
+        match event.kind {
+            SessionEventKind::Delivery => self.deliveries += 1,
+            SessionEventKind::Abandon => self.abandonments += 1,
+            _ => {}
+        }
+        self.events.push(event);
+    }
+
+    /// Humans delivered so far today
+    pub fn deliveries(&self) -> usize {
+        self.deliveries
+    }
+
+    /// Humans who ran out of patience so far today, waiting or riding
+    pub fn abandonments(&self) -> usize {
+        self.abandonments
+    }
+
+    /// Mean seconds between a human appearing and being picked up, with those still waiting
+    /// counted up to [now_secs], and those who gave up counted up to then. Humans on a floor
+    /// are picked up first come first served, so pickups are matched to spawns in order per
+    /// floor and destination, as are those giving up (the first to arrive runs out first)
+    pub fn average_wait_secs(&self, now_secs: f32) -> Option<f32> {
+        let mut waiting: HashMap<(i32, Option<i32>), VecDeque<f32>> = HashMap::new();
+        let mut total_wait = 0.0;
+        let mut num_waits = 0;
+        for event in self.events.iter() {
+            let key = (event.floor, event.destination_floor);
+            match event.kind {
+                SessionEventKind::Spawn => {
+                    waiting.entry(key).or_default().push_back(event.time_secs)
+                }
+                // Riders giving up are keyed by their destination, which no queue has
+                SessionEventKind::Pickup | SessionEventKind::Abandon => {
+                    if let Some(spawned) = waiting.get_mut(&key).and_then(VecDeque::pop_front) {
+                        total_wait += event.time_secs - spawned;
+                        num_waits += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+        for spawned in waiting.values().flatten() {
+            total_wait += now_secs - spawned;
+            num_waits += 1;
+        }
+        (num_waits > 0).then(|| total_wait / num_waits as f32)
+    }
+
+    pub fn write_events_csv(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        writer.write_all(EVENT_CSV_HEADER.as_bytes())?;
+        for event in self.events.iter() {
+            writer.write_all(event.to_csv().as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+fn write_history_csv(path: &Path, log: &HistoryStore<(f32, f32)>) -> std::io::Result<()> {
+    let mut csv = String::from("time_secs,value\n");
+    for (time, value) in log.iter_merged() {
+        csv.push_str(&format!("{},{}\n", time, value));
+    }
+    fs::write(path, csv)
+}
+
+fn export_session(
+    day: usize,
+    config: &GameTimeConfig,
+    session_log: &SessionLog,
+    logs: [(&str, &HistoryStore<(f32, f32)>); 4],
+) -> std::io::Result<PathBuf> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let dir = Path::new(SESSIONS_DIR).join(format!("day_{}_{}", day, timestamp));
+    fs::create_dir_all(&dir)?;
+
+    let mut events_file = fs::File::create(dir.join("events.csv"))?;
+    events_file.write_all(csv_preamble(config).as_bytes())?;
+    session_log.write_events_csv(&mut events_file)?;
+
+    for (name, log) in logs {
+        write_history_csv(&dir.join(format!("{}.csv", name)), log)?;
+    }
+    Ok(dir)
+}
+
+/// Export at the end of the day, or whenever asked to
+fn export_session_system(
+    mut day_ended_events: EventReader<DayEndedEvent>,
+    inputs: Query<&ActionState<InputAction>>,
+    game_central_info: Res<GameCentralInfo>,
+    session_log: Res<SessionLog>,
+    velocity_log: Res<VelocityLog>,
+    observed_velocity_log: Res<ObservedVelocityLog>,
+    acceleration_log: Res<AccelerationLog>,
+    jerk_log: Res<JerkLog>,
+) {
+    let day_ended = day_ended_events.read().count() > 0;
+    let requested = inputs.get_single().map_or(false, |inputs| {
+        inputs.just_pressed(&InputAction::ExportSession)
+    });
+    if !day_ended && !requested {
+        return;
+    }
+    let logs = [
+        ("velocity", &velocity_log.0),
+        ("observed_velocity", &observed_velocity_log.0),
+        ("acceleration", &acceleration_log.0),
+        ("jerk", &jerk_log.0),
+    ];
+    match export_session(
+        game_central_info.day(),
+        game_central_info.time.config(),
+        &session_log,
+        logs,
+    ) {
+        Ok(dir) => info!("Exported session to {}", dir.display()),
+        Err(error) => warn!("Failed to export session: {:?}", error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_csv() {
+        let mut session_log = SessionLog::default();
+        session_log.push(SessionEvent::spawn(1.5, 2, 7));
+        session_log.push(SessionEvent::delivery(20.0, 7, 0.5, 2.5));
+        let mut csv = vec![];
+        session_log.write_events_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "time_secs,event,floor,destination_floor,comfort,tip\n\
+             1.5,spawn,2,7,,\n\
+             20,delivery,7,,0.5,2.5\n"
+        );
+    }
+
+    #[test]
+    fn test_average_wait() {
+        let mut session_log = SessionLog::default();
+        assert_eq!(session_log.average_wait_secs(10.0), None);
+        session_log.push(SessionEvent::spawn(0.0, 2, 7));
+        session_log.push(SessionEvent::spawn(4.0, 2, 7));
+        session_log.push(SessionEvent::spawn(5.0, 3, 0));
+        session_log.push(SessionEvent::pickup(10.0, 2, 7));
+        session_log.push(SessionEvent::abandon(30.0, 7));
+        // 10s for the first on floor 2, then the others are still waiting at 20s
+        assert_eq!(
+            session_log.average_wait_secs(20.0),
+            Some((10.0 + 16.0 + 15.0) / 3.0)
+        );
+        assert_eq!(session_log.abandonments(), 1);
+        assert_eq!(session_log.deliveries(), 0);
+        // Giving up stops the wait, just as being picked up does
+        session_log.push(SessionEvent::abandon_waiting(35.0, 3, 0));
+        assert_eq!(
+            session_log.average_wait_secs(40.0),
+            Some((10.0 + 36.0 + 30.0) / 3.0)
+        );
+        assert_eq!(session_log.abandonments(), 2);
+    }
+}
@@ -0,0 +1,103 @@
+use crate::game::game::DayScopedResourceExt;
+use crate::input_action::InputAction;
+use crate::{GameState, PauseState};
+use bevy::prelude::*;
+use leafwing_input_manager::action_state::ActionState;
+
+pub struct TimeScalePlugin;
+
+impl Plugin for TimeScalePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                TimeScale::input_system.run_if(in_state(PauseState::Running)),
+                TimeScale::apply_system,
+            )
+                .chain()
+                .run_if(in_state(GameState::PlayingDay)),
+        )
+        .init_day_resource::<TimeScale>()
+        .register_type::<TimeScale>()
+        .register_type::<GameSpeed>();
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Reflect)]
+pub enum GameSpeed {
+    #[default]
+    Normal,
+    Double,
+    Quadruple,
+    Octuple,
+}
+
+impl GameSpeed {
+    pub fn factor(&self) -> f32 {
+        match self {
+            Self::Normal => 1.0,
+            Self::Double => 2.0,
+            Self::Quadruple => 4.0,
+            Self::Octuple => 8.0,
+        }
+    }
+
+    fn faster(&self) -> Self {
+        match self {
+            Self::Normal => Self::Double,
+            Self::Double => Self::Quadruple,
+            Self::Quadruple | Self::Octuple => Self::Octuple,
+        }
+    }
+
+    fn slower(&self) -> Self {
+        match self {
+            Self::Normal | Self::Double => Self::Normal,
+            Self::Quadruple => Self::Double,
+            Self::Octuple => Self::Quadruple,
+        }
+    }
+}
+
+/// Scales everything that runs off virtual time, which is the clock, spawning, patience, tweens
+/// and the lift physics in [FixedUpdate]
+#[derive(Resource, Debug, Default, Reflect)]
+pub struct TimeScale {
+    pub speed: GameSpeed,
+    pub paused: bool,
+}
+
+impl TimeScale {
+    pub fn label(&self) -> String {
+        if self.paused {
+            "Paused".to_string()
+        } else {
+            format!("x{}", self.speed.factor())
+        }
+    }
+
+    fn input_system(inputs: Query<&ActionState<InputAction>>, mut time_scale: ResMut<Self>) {
+        for inputs in inputs.iter() {
+            if inputs.just_pressed(&InputAction::TogglePauseTime) {
+                time_scale.paused = !time_scale.paused;
+            }
+            if inputs.just_pressed(&InputAction::SpeedUp) {
+                time_scale.speed = time_scale.speed.faster();
+            }
+            if inputs.just_pressed(&InputAction::SlowDown) {
+                time_scale.speed = time_scale.speed.slower();
+            }
+        }
+    }
+
+    fn apply_system(time_scale: Res<Self>, mut time: ResMut<Time<Virtual>>) {
+        if time_scale.is_changed() {
+            time.set_relative_speed(time_scale.speed.factor());
+            if time_scale.paused {
+                time.pause();
+            } else {
+                time.unpause();
+            }
+        }
+    }
+}
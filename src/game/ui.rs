@@ -1,6 +1,8 @@
-use crate::game::game::{AccelerationLog, ObservedVelocity, ObservedVelocityLog, VelocityLog};
-use crate::game::game_clock::GameTime;
-use crate::game::lift::LiftHumanStore;
+use crate::game::game::DaySimulationSet;
+use crate::game::game_clock::{GameTime, GameTimeConfig};
+use crate::game::lift::RideReport;
+use crate::game::spawn_simulation::FloorSpawnManager;
+use crate::game::telemetry::DayEndedEvent;
 use crate::GameState;
 use bevy::prelude::*;
 use bevy::time::Stopwatch;
@@ -8,7 +10,6 @@ use bevy::utils::hashbrown::HashMap;
 use bevy_egui::egui::{Align2, Color32, Frame, RichText, Rounding};
 use bevy_egui::{egui, EguiContexts};
 use egui_extras::{Column, TableBuilder};
-use egui_plot::{Line, Plot, PlotPoints};
 
 pub struct GameUiPlugin;
 
@@ -16,153 +17,21 @@ impl Plugin for GameUiPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
             Update,
-            (
-                recalculate_plot_points,
-                show_ui,
-                GameCentralInfo::update_system,
-                LiftHumanStore::draw_system,
-            )
-                .run_if(in_state(GameState::PlayingDay)),
+            show_events_system.run_if(in_state(GameState::PlayingDay)),
         )
-        .init_resource::<ShowUiState>()
-        .insert_resource(GameCentralInfo::new())
-        .insert_resource(TrueVelocityPlotPoints(vec![]))
-        .insert_resource(ObservedVelocityPlotPoints(vec![]))
-        .insert_resource(AccelerationPlotPoints(vec![]));
-    }
-}
-
-#[derive(Resource)]
-struct ShowUiState {
-    show_velocity: bool,
-    show_acceleration: bool,
-    show_smooth_acceleration: bool,
-    show_observed_velocity: bool,
-}
-impl Default for ShowUiState {
-    fn default() -> Self {
-        Self {
-            show_velocity: true,
-            show_acceleration: true,
-            show_smooth_acceleration: true,
-            show_observed_velocity: true,
-        }
-    }
-}
-
-fn show_ui(
-    mut contexts: EguiContexts,
-    target_velocity_plot_points: Res<TrueVelocityPlotPoints>,
-    observed_velocity_plot_points: Res<ObservedVelocityPlotPoints>,
-    acceleration_plot_points: Res<AccelerationPlotPoints>,
-    mut show_state: ResMut<ShowUiState>,
-) {
-    egui::Window::new("Ui").show(contexts.ctx_mut(), |ui| {
-        ui.checkbox(&mut show_state.show_velocity, "Show Velocity");
-        ui.checkbox(&mut show_state.show_acceleration, "Show Acceleration");
-        ui.checkbox(
-            &mut show_state.show_smooth_acceleration,
-            "Show Smooth Acceleration",
-        );
-        ui.checkbox(&mut show_state.show_observed_velocity, "Show Obs Velocity");
-        Plot::new("Plot").view_aspect(2.0).show(ui, |ui| {
-            if show_state.show_velocity {
-                let velocity_points = PlotPoints::new(target_velocity_plot_points.0.clone());
-                ui.line(
-                    Line::new(velocity_points)
-                        .name("Velocity")
-                        .color(Color32::GREEN),
-                );
-            }
-            if show_state.show_acceleration {
-                let accel_points = PlotPoints::new(acceleration_plot_points.0.clone());
-                ui.line(
-                    Line::new(accel_points)
-                        .name("Acceleration")
-                        .color(Color32::RED),
-                );
-            }
-            if show_state.show_smooth_acceleration {
-                let accel_points = {
-                    let mut prev: Option<[f64; 2]> = None;
-                    let points: Vec<[f64; 2]> = acceleration_plot_points
-                        .0
-                        .iter()
-                        .map(|point| match prev {
-                            Some(prev_point) => {
-                                let x = (prev_point[0] + point[0]) / 2.0;
-                                let y = (prev_point[1] + point[1]) / 2.0;
-                                prev = Some(*point);
-                                [x, y]
-                            }
-                            None => {
-                                prev = Some(*point);
-                                *point
-                            }
-                        })
-                        .collect();
-                    PlotPoints::new(points)
-                };
-                ui.line(
-                    Line::new(accel_points)
-                        .name("Smoothed Acceleration")
-                        .color(Color32::YELLOW),
-                );
-            }
-            if show_state.show_observed_velocity {
-                let obs_velocity_points = PlotPoints::new(observed_velocity_plot_points.0.clone());
-                ui.line(
-                    Line::new(obs_velocity_points)
-                        .name("Observed Velocity")
-                        .color(Color32::DARK_GREEN),
-                );
-            }
-        });
-    });
-}
-
-fn recalculate_plot_points(
-    velocity_log: Res<VelocityLog>,
-    observed_velocity_log: Res<ObservedVelocityLog>,
-    acceleration_log: Res<AccelerationLog>,
-    mut velocity_plot: ResMut<TrueVelocityPlotPoints>,
-    mut obs_velocity_plot: ResMut<ObservedVelocityPlotPoints>,
-    mut acceleration_plot: ResMut<AccelerationPlotPoints>,
-    mut count: Local<usize>,
-) {
-    // This means plot update is framerate linked, but meh
-    *count = (*count + 1usize) % 3;
-    if *count == 0 {
-        let points: Vec<[f64; 2]> = velocity_log
-            .0
-            .iter_primary()
-            .map(|(time, val)| [*time as f64, *val as f64])
-            .collect();
-        velocity_plot.0 = points;
-    } else if *count == 1 {
-        let points: Vec<[f64; 2]> = acceleration_log
-            .0
-            .iter_primary()
-            .map(|(time, val)| [*time as f64, *val as f64])
-            .collect();
-        acceleration_plot.0 = points;
-    } else if *count == 2 {
-        let points: Vec<[f64; 2]> = observed_velocity_log
-            .0
-            .iter_primary()
-            .map(|(time, val)| [*time as f64, *val as f64])
-            .collect();
-        obs_velocity_plot.0 = points;
+        .add_systems(
+            FixedUpdate,
+            GameCentralInfo::tick_system.in_set(DaySimulationSet::Clock),
+        )
+        .add_systems(
+            OnEnter(GameState::PlayingDay),
+            GameCentralInfo::start_day_system,
+        )
+        .init_resource::<GameTimeConfig>()
+        .insert_resource(GameCentralInfo::new());
     }
 }
 
-#[derive(Resource, Debug)]
-pub struct TrueVelocityPlotPoints(Vec<[f64; 2]>);
-#[derive(Resource, Debug)]
-pub struct ObservedVelocityPlotPoints(Vec<[f64; 2]>);
-#[derive(Resource, Debug)]
-pub struct AccelerationPlotPoints(Vec<[f64; 2]>);
-
 pub fn default_frame() -> Frame {
     Frame::default()
         .inner_margin(4.0)
@@ -173,9 +42,15 @@ pub fn default_frame() -> Frame {
         })
 }
 
+const STARTING_REPUTATION: f32 = 0.5;
+/// How much each delivery moves the reputation towards that rider's rating
+const REPUTATION_RESPONSIVENESS: f32 = 0.1;
+
 #[derive(Resource, Debug, Reflect)]
 pub struct GameCentralInfo {
     money: f32,
+    /// How well thought of the lift service is, from 0.0 to 1.0, built up by deliveries
+    reputation: f32,
     day: usize,
     pub time: GameTime,
 }
@@ -184,38 +59,110 @@ impl GameCentralInfo {
     pub fn new() -> Self {
         Self {
             money: 0.0,
+            reputation: STARTING_REPUTATION,
             day: 1,
             time: GameTime::new(),
         }
     }
 
-    fn update_system(mut info: ResMut<Self>, mut contexts: EguiContexts, time: Res<Time>) {
+    pub fn day(&self) -> usize {
+        self.day
+    }
+
+    pub fn money(&self) -> f32 {
+        self.money
+    }
+
+    pub fn reputation(&self) -> f32 {
+        self.reputation
+    }
+
+    pub fn add_money(&mut self, amount: f32) {
+        self.money += amount;
+    }
+
+    /// Take the rider's tip, and let their opinion of the ride (half comfort, half how long
+    /// they were kept waiting) nudge the reputation
+    pub fn record_delivery(&mut self, report: &RideReport) {
+        self.add_money(report.tip());
+        let rating = 0.5 * report.comfort + 0.5 * report.patience_fraction;
+        self.reputation += (rating - self.reputation) * REPUTATION_RESPONSIVENESS;
+    }
+
+    /// Start afresh, with the clock using the configured day length and working hours. Kept
+    /// after the day ends rather than being a day resource, for the screens that follow it
+    fn start_day_system(mut info: ResMut<Self>, config: Res<GameTimeConfig>) {
+        *info = Self {
+            time: GameTime::with_config(config.clone()),
+            ..Self::new()
+        };
+    }
+
+    fn tick_system(
+        mut info: ResMut<Self>,
+        time: Res<Time>,
+        mut day_ended_events: EventWriter<DayEndedEvent>,
+    ) {
         info.time.tick(time.delta());
-        let frame = default_frame();
-        let text_color = Color32::WHITE;
-        let size = 24.0;
-        egui::Window::new("Game Info")
-            .movable(false)
-            .resizable(false)
-            .anchor(Align2::RIGHT_BOTTOM, bevy_egui::egui::Vec2::ZERO)
-            .title_bar(false)
-            .frame(frame)
-            .show(contexts.ctx_mut(), |ui| {
-                ui.label(
-                    RichText::new(format!("Day: {}", info.day))
-                        .color(text_color)
-                        .size(size),
-                );
+        if info.time.just_finished() {
+            day_ended_events.send(DayEndedEvent { day: info.day });
+        }
+    }
+}
+
+/// How far ahead, in game minutes, upcoming events are announced
+const EVENT_ANNOUNCE_MINUTES: u16 = 120;
+
+fn show_events_system(
+    mut contexts: EguiContexts,
+    info: Res<GameCentralInfo>,
+    floor_spawn_manager: Option<Res<FloorSpawnManager>>,
+) {
+    let Some(floor_spawn_manager) = floor_spawn_manager else {
+        return;
+    };
+    let now = info.time.to_game_time_of_day();
+    let active: Vec<_> = floor_spawn_manager.active_events().collect();
+    let upcoming: Vec<_> = floor_spawn_manager
+        .schedule()
+        .upcoming(&now, EVENT_ANNOUNCE_MINUTES)
+        .collect();
+    if active.is_empty() && upcoming.is_empty() {
+        return;
+    }
+    let text_color = Color32::WHITE;
+    let size = 18.0;
+    egui::Window::new("Events")
+        .movable(false)
+        .resizable(false)
+        .anchor(Align2::CENTER_TOP, egui::Vec2::ZERO)
+        .title_bar(false)
+        .frame(default_frame())
+        .show(contexts.ctx_mut(), |ui| {
+            for event in active {
                 ui.label(
-                    RichText::new(format!("Money: {}", info.money))
-                        .color(text_color)
-                        .size(size),
+                    RichText::new(format!(
+                        "NOW until {}: {} - {}",
+                        event.end,
+                        event.name,
+                        event.kind.describe()
+                    ))
+                    .color(Color32::YELLOW)
+                    .size(size),
                 );
+            }
+            for event in upcoming {
                 ui.label(
-                    RichText::new(format!("{}", info.time.to_game_time_of_day()))
-                        .color(text_color)
-                        .size(size),
+                    RichText::new(format!(
+                        "{}-{}: {} - {}",
+                        event.start,
+                        event.end,
+                        event.name,
+                        event.kind.describe()
+                    ))
+                    .color(text_color)
+                    .size(size),
                 );
-            });
-    }
+            }
+        });
 }
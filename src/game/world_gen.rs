@@ -1,6 +1,11 @@
+use crate::game::spawn_simulation::{prefabs, RawFloorConfig};
+use bevy::prelude::Resource;
 use rand::Rng;
 use std::ops::Range;
 
+/// Regular floors in the building when nothing says otherwise
+pub const DEFAULT_FLOOR_COUNT: usize = 10;
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Class {
     One,
@@ -20,16 +25,20 @@ pub enum LevelKind {
 pub enum FloorKind {
     Regular,
     Lobby,
+    Restaurant,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Floor {
     kind: FloorKind,
     number: usize,
+    /// Who comes and goes from the floor, if designed rather than left to the prefabs
+    spawn_config: Option<RawFloorConfig>,
 }
 
 // TODO: Consider generic over floor where floor corresponds to kind
-#[derive(Debug, PartialEq)]
+/// The building the next day is played in, read by [crate::game::floors::build_floor_map]
+#[derive(Resource, Clone, Debug, PartialEq)]
 pub struct Level {
     kind: LevelKind,
     floors: Vec<Floor>,
@@ -49,13 +58,111 @@ impl LevelKind {
     }
 }
 
+impl FloorKind {
+    pub const ALL: [Self; 3] = [Self::Regular, Self::Lobby, Self::Restaurant];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Regular => "Regular",
+            Self::Lobby => "Lobby",
+            Self::Restaurant => "Restaurant",
+        }
+    }
+
+    /// What floor [floor_num] of a generated building is
+    pub fn usual_of_floor_num(floor_num: i32) -> Self {
+        match floor_num {
+            prefabs::LOBBY_FLOOR_NUM => Self::Lobby,
+            prefabs::RESTAURANT_FLOOR_NUM => Self::Restaurant,
+            _ => Self::Regular,
+        }
+    }
+
+    /// Who usually comes and goes from a floor of this kind. Regular floors vary with how high
+    /// up [floor_num] is
+    pub fn prefab_config(&self, floor_num: i32) -> RawFloorConfig {
+        match self {
+            Self::Regular => prefabs::regular_config(floor_num),
+            Self::Lobby => prefabs::lobby_config(),
+            Self::Restaurant => prefabs::restaurant_config(),
+        }
+    }
+}
+
 impl Floor {
     fn new(number: usize, kind: FloorKind) -> Self {
-        Self { kind, number }
+        Self {
+            kind,
+            number,
+            spawn_config: None,
+        }
+    }
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Self::with_floor_count(LevelKind::Hotel, Class::One, DEFAULT_FLOOR_COUNT)
     }
 }
 
 impl Level {
+    /// A level with exactly [floor_count] floors, rather than a random count for its [class],
+    /// each the usual kind for its height
+    pub fn with_floor_count(kind: LevelKind, class: Class, floor_count: usize) -> Self {
+        // Index 0 is the lowest served floor, 1F, see [crate::game::floors::build_floor_map]
+        let floors = (0..floor_count)
+            .map(|i| Floor::new(i, FloorKind::usual_of_floor_num(i as i32 + 1)))
+            .collect();
+        Self {
+            kind,
+            floors,
+            class,
+        }
+    }
+
+    /// A designed level, see [crate::scenario_editor]. [floors] run from the bottom up
+    pub fn with_floors(
+        kind: LevelKind,
+        class: Class,
+        floors: Vec<(FloorKind, RawFloorConfig)>,
+    ) -> Self {
+        let floors = floors
+            .into_iter()
+            .enumerate()
+            .map(|(i, (floor_kind, spawn_config))| Floor {
+                spawn_config: Some(spawn_config),
+                ..Floor::new(i, floor_kind)
+            })
+            .collect();
+        Self {
+            kind,
+            floors,
+            class,
+        }
+    }
+
+    pub fn floor_count(&self) -> usize {
+        self.floors.len()
+    }
+
+    /// Who comes and goes from the floor [index] up from the bottom, numbered [floor_num]. As
+    /// designed if it was, else the usual for its kind
+    pub fn spawn_config(&self, index: usize, floor_num: i32) -> RawFloorConfig {
+        match self.floors.get(index) {
+            Some(Floor {
+                spawn_config: Some(spawn_config),
+                ..
+            }) => spawn_config.clone(),
+            Some(floor) => floor.kind.prefab_config(floor_num),
+            None => FloorKind::Regular.prefab_config(floor_num),
+        }
+    }
+
+    /// Whether any floor was designed rather than generated
+    pub fn is_designed(&self) -> bool {
+        self.floors.iter().any(|floor| floor.spawn_config.is_some())
+    }
+
     pub fn create(kind: LevelKind, class: Class, rng: &mut impl Rng) -> Self {
         let possible_floor_range = kind.to_floor_count_of_class(class);
         let floor_count = rng.gen_range(possible_floor_range);
@@ -99,4 +206,23 @@ mod tests {
         };
         assert_eq!(level, expected);
     }
+
+    #[test]
+    fn test_generated_kinds() {
+        let level = Level::with_floor_count(LevelKind::Hotel, Class::One, 8);
+        let kinds: Vec<FloorKind> = level.floors.iter().map(|floor| floor.kind).collect();
+        assert_eq!(kinds[0], FloorKind::Lobby);
+        assert_eq!(kinds[5], FloorKind::Restaurant);
+        assert_eq!(kinds[1], FloorKind::Regular);
+        assert_eq!(level.spawn_config(0, 1), FloorKind::Lobby.prefab_config(1));
+        // Choosing regular for the lobby's floor really does make it regular
+        assert_ne!(
+            FloorKind::Regular.prefab_config(1),
+            FloorKind::Lobby.prefab_config(1)
+        );
+        assert_ne!(
+            FloorKind::Regular.prefab_config(6),
+            FloorKind::Restaurant.prefab_config(6)
+        );
+    }
 }
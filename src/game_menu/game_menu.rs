@@ -1,17 +1,48 @@
+use crate::game::campaign::{self, ActiveCampaignDay, CampaignProgress, CAMPAIGN_DAYS};
+use crate::game::endless::{self, EndlessRun, HighScores};
+use crate::game::game_clock::GameTimeConfig;
+use crate::game::replay::{self, Replay};
+use crate::game::seed::DaySeed;
+use crate::game::world_gen::Level;
 use crate::GameState;
 use crate::GameState::PlayingMenu;
 use bevy::prelude::*;
 use bevy_egui::egui::Layout;
 use bevy_egui::{egui, EguiContexts, EguiPlugin, EguiSettings};
+use std::path::PathBuf;
 
 pub struct MenuPlugin;
 
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, day_menu.run_if(in_state(PlayingMenu)));
+        app.add_systems(OnEnter(PlayingMenu), SavedReplays::refresh_system)
+            .add_systems(Update, day_menu.run_if(in_state(PlayingMenu)))
+            .init_resource::<SavedReplays>();
     }
 }
-fn day_menu(mut contexts: EguiContexts, mut next_state: ResMut<NextState<GameState>>) {
+
+/// The replays on disk, listed on entering the menu rather than every frame
+#[derive(Resource, Debug, Default)]
+struct SavedReplays(Vec<PathBuf>);
+
+impl SavedReplays {
+    fn refresh_system(mut saved_replays: ResMut<Self>) {
+        saved_replays.0 = Replay::list_saved();
+    }
+}
+
+fn day_menu(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut day_seed: ResMut<DaySeed>,
+    mut time_config: ResMut<GameTimeConfig>,
+    mut level: ResMut<Level>,
+    progress: Res<CampaignProgress>,
+    high_scores: Res<HighScores>,
+    saved_replays: Res<SavedReplays>,
+    mut seed_text: Local<String>,
+) {
     let ctx = contexts.ctx_mut();
 
     egui::TopBottomPanel::bottom("bottom_panel").show(ctx, |ui| {
@@ -19,7 +50,32 @@ fn day_menu(mut contexts: EguiContexts, mut next_state: ResMut<NextState<GameSta
             egui::TextStyle::Body,
             egui::FontId::new(20.0, egui::FontFamily::Proportional),
         );
-        ui.label("Day 1");
+        ui.horizontal_wrapped(|ui| {
+            for (index, day) in CAMPAIGN_DAYS.iter().enumerate() {
+                let stars = campaign::stars_text(progress.stars(index));
+                let button = ui
+                    .add_enabled(
+                        progress.is_unlocked(index),
+                        egui::Button::new(format!("Day {}: {}\n{}", index + 1, day.name, stars)),
+                    )
+                    .on_hover_ui(|ui| {
+                        for objective in day.objectives.iter() {
+                            ui.label(objective.describe());
+                        }
+                    })
+                    .on_disabled_hover_text("Earn a star on the day before to unlock");
+                if button.clicked() {
+                    campaign::start_campaign_day(
+                        index,
+                        &mut commands,
+                        &mut day_seed,
+                        &mut time_config,
+                        &mut level,
+                    );
+                    next_state.set(GameState::PlayingDay);
+                }
+            }
+        });
     });
     egui::TopBottomPanel::bottom("bottom_panel_two").show(ctx, |ui| {
         ui.style_mut().text_styles.insert(
@@ -40,9 +96,94 @@ fn day_menu(mut contexts: EguiContexts, mut next_state: ResMut<NextState<GameSta
                 ui.vertical_centered(|ui| {
                     let start_day_button = ui.button("Start Day");
                     if start_day_button.clicked() {
-                        info!("Start Day");
+                        // A fresh day each time, unless asked to replay a particular seed
+                        *day_seed = seed_text
+                            .trim()
+                            .parse()
+                            .map_or_else(|_| DaySeed::default(), DaySeed);
+                        *time_config = GameTimeConfig::default();
+                        *level = Level::default();
+                        commands.remove_resource::<ActiveCampaignDay>();
+                        commands.remove_resource::<EndlessRun>();
+                        info!("Start Day (seed {})", day_seed.0);
                         next_state.set(GameState::PlayingDay);
                     }
+                    let mut endless_seed = None;
+                    if ui.button("Endless").clicked() {
+                        endless_seed = Some(
+                            seed_text
+                                .trim()
+                                .parse()
+                                .unwrap_or_else(|_| DaySeed::default().0),
+                        );
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("Seed");
+                        ui.add(egui::TextEdit::singleline(&mut *seed_text).hint_text("random"));
+                    });
+                    if high_scores.iter().next().is_some() {
+                        ui.collapsing("High Scores", |ui| {
+                            if let Some(seed) =
+                                endless::high_scores_ui(ui, &high_scores, None, true)
+                            {
+                                endless_seed = Some(seed);
+                            }
+                        });
+                    }
+                    if let Some(seed) = endless_seed {
+                        endless::start_endless_run(
+                            seed,
+                            &mut commands,
+                            &mut day_seed,
+                            &mut time_config,
+                            &mut level,
+                        );
+                        next_state.set(GameState::PlayingDay);
+                    }
+                    let replays = &saved_replays.0;
+                    if !replays.is_empty() {
+                        ui.collapsing("Replays", |ui| {
+                            egui::ScrollArea::vertical()
+                                .max_height(200.0)
+                                .show(ui, |ui| {
+                                    for path in replays.iter().rev() {
+                                        ui.horizontal(|ui| {
+                                            let name =
+                                                path.file_stem().map_or(String::new(), |name| {
+                                                    name.to_string_lossy().to_string()
+                                                });
+                                            ui.label(name);
+                                            if ui.button("Watch").clicked() {
+                                                match Replay::load(path) {
+                                                    Ok(loaded) => {
+                                                        replay::start_replay(
+                                                            loaded,
+                                                            None,
+                                                            &mut commands,
+                                                            &mut day_seed,
+                                                            &mut time_config,
+                                                            &mut level,
+                                                        );
+                                                        commands
+                                                            .remove_resource::<ActiveCampaignDay>();
+                                                        commands.remove_resource::<EndlessRun>();
+                                                        next_state.set(GameState::PlayingDay);
+                                                    }
+                                                    Err(error) => warn!(
+                                                        "Failed to load {}: {}",
+                                                        path.display(),
+                                                        error
+                                                    ),
+                                                }
+                                            }
+                                        });
+                                    }
+                                });
+                        });
+                    }
+                    if ui.button("Scenario Editor").clicked() {
+                        next_state.set(GameState::ScenarioEditor);
+                    }
                     let quit_button = ui.button("Quit");
                     if quit_button.clicked() {
                         info!("Quit");
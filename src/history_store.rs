@@ -103,7 +103,7 @@ impl<'a, T> Iterator for SimpleRingbufIter<'a, T> {
 pub struct HistoryStore<T> {
     primary_ringbuf: SimpleRingbuf<T>,
     storage_ringbuf: SimpleRingbuf<T>,
-    persistence_method: PersistenceMethod,
+    persistence_method: PersistenceMethod<T>,
 }
 
 impl<T: Default + Debug + Clone> HistoryStore<T> {
@@ -111,19 +111,31 @@ impl<T: Default + Debug + Clone> HistoryStore<T> {
         primary_size: usize,
         storage_size: usize,
         store_every_nth: usize,
+    ) -> HistoryStore<T> {
+        Self::with_persistence(
+            primary_size,
+            storage_size,
+            PersistenceMethod::KeepEvery(KeepEveryMethod::new(store_every_nth)),
+        )
+    }
+
+    pub fn with_persistence(
+        primary_size: usize,
+        storage_size: usize,
+        persistence_method: PersistenceMethod<T>,
     ) -> HistoryStore<T> {
         Self {
             primary_ringbuf: SimpleRingbuf::new(primary_size),
             storage_ringbuf: SimpleRingbuf::new(storage_size),
-            persistence_method: PersistenceMethod::KeepEvery(KeepEveryMethod::new(store_every_nth)),
+            persistence_method,
         }
     }
 
     pub fn push(&mut self, value: T) {
         if let Some(replaced) = self.primary_ringbuf.push_emit(value) {
-            if self.persistence_method.persist_next() {
-                self.storage_ringbuf.push(replaced);
-            }
+            let storage_ringbuf = &mut self.storage_ringbuf;
+            self.persistence_method
+                .persist(replaced, |persisted| storage_ringbuf.push(persisted));
         }
     }
 }
@@ -135,21 +147,99 @@ where
     pub fn iter_primary(&'a self) -> impl Iterator<Item = &T> + 'a {
         self.primary_ringbuf.iter()
     }
+
+    /// The down-sampled history of values which have fallen out of the primary ringbuf
+    pub fn iter_storage(&'a self) -> impl Iterator<Item = &T> + 'a {
+        self.storage_ringbuf.iter()
+    }
+
+    /// Everything held, oldest first. Everything in storage is older than everything in
+    /// primary, so this is just storage followed by primary, though the resolution jumps up
+    /// where they meet.
+    pub fn iter_merged(&'a self) -> impl Iterator<Item = &T> + 'a {
+        self.iter_storage().chain(self.iter_primary())
+    }
 }
 
+/// How values falling out of the primary ringbuf are reduced into the storage ringbuf
 #[derive(Debug, Clone)]
-pub enum PersistenceMethod {
+pub enum PersistenceMethod<T> {
     KeepEvery(KeepEveryMethod),
+    MinMaxMean(MinMaxMeanMethod<T>),
+    TimeBucket(TimeBucketMethod<T>),
+    Lttb(LttbMethod<T>),
 }
 
-impl PersistenceMethod {
-    fn persist_next(&mut self) -> bool {
+impl<T: Clone> PersistenceMethod<T> {
+    /// Take the next value leaving the primary ringbuf, calling [store] with anything that
+    /// should be persisted as a result (possibly nothing, possibly several values)
+    fn persist(&mut self, value: T, mut store: impl FnMut(T)) {
         match self {
-            PersistenceMethod::KeepEvery(method) => method.persist_next(),
+            PersistenceMethod::KeepEvery(method) => {
+                if method.persist_next() {
+                    store(value)
+                }
+            }
+            PersistenceMethod::MinMaxMean(method) => method.persist(value, store),
+            PersistenceMethod::TimeBucket(method) => method.persist(value, store),
+            PersistenceMethod::Lttb(method) => method.persist(value, store),
         }
     }
 }
 
+/// A timestamped value, which the aggregating [PersistenceMethod]s know how to combine
+pub trait Sample {
+    fn time(&self) -> f32;
+    fn value(&self) -> f32;
+    fn of_time_and_value(time: f32, value: f32) -> Self;
+}
+
+impl Sample for (f32, f32) {
+    fn time(&self) -> f32 {
+        self.0
+    }
+    fn value(&self) -> f32 {
+        self.1
+    }
+    fn of_time_and_value(time: f32, value: f32) -> Self {
+        (time, value)
+    }
+}
+
+/// [Sample]'s functions, captured when a method is made so [PersistenceMethod] itself doesn't
+/// need to require [Sample] (which would rule out [KeepEveryMethod] for anything else)
+#[derive(Clone, Copy)]
+struct SampleFns<T> {
+    time: fn(&T) -> f32,
+    value: fn(&T) -> f32,
+    of_time_and_value: fn(f32, f32) -> T,
+}
+
+impl<T: Sample> SampleFns<T> {
+    fn new() -> Self {
+        Self {
+            time: T::time,
+            value: T::value,
+            of_time_and_value: T::of_time_and_value,
+        }
+    }
+}
+
+impl<T> SampleFns<T> {
+    fn mean(&self, samples: &[T]) -> T {
+        let n = samples.len() as f32;
+        let time = samples.iter().map(self.time).sum::<f32>() / n;
+        let value = samples.iter().map(self.value).sum::<f32>() / n;
+        (self.of_time_and_value)(time, value)
+    }
+}
+
+impl<T> Debug for SampleFns<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SampleFns")
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct KeepEveryMethod {
     nth: usize,
@@ -167,6 +257,147 @@ impl KeepEveryMethod {
     }
 }
 
+/// Every [n] samples, keep the smallest, the largest and the mean, so spikes are never lost
+#[derive(Debug, Clone)]
+pub struct MinMaxMeanMethod<T> {
+    n: usize,
+    bucket: Vec<T>,
+    fns: SampleFns<T>,
+}
+
+impl<T: Sample + Clone> MinMaxMeanMethod<T> {
+    pub fn new(n: usize) -> MinMaxMeanMethod<T> {
+        MinMaxMeanMethod {
+            n,
+            bucket: Vec::with_capacity(n),
+            fns: SampleFns::new(),
+        }
+    }
+}
+
+impl<T: Clone> MinMaxMeanMethod<T> {
+    fn persist(&mut self, value: T, mut store: impl FnMut(T)) {
+        self.bucket.push(value);
+        if self.bucket.len() < self.n {
+            return;
+        }
+        let value_of = self.fns.value;
+        let by_value = |a: &&T, b: &&T| value_of(a).total_cmp(&value_of(b));
+        // Buckets are never empty here, so these are always Some
+        if let (Some(min), Some(max)) = (
+            self.bucket.iter().min_by(by_value),
+            self.bucket.iter().max_by(by_value),
+        ) {
+            let mut aggregated = vec![min.clone(), max.clone(), self.fns.mean(&self.bucket)];
+            aggregated.sort_by(|a, b| (self.fns.time)(a).total_cmp(&(self.fns.time)(b)));
+            aggregated.into_iter().for_each(&mut store);
+        }
+        self.bucket.clear();
+    }
+}
+
+/// Keep the mean of each [bucket_seconds] long stretch of time, regardless of how many samples
+/// fall in it
+#[derive(Debug, Clone)]
+pub struct TimeBucketMethod<T> {
+    bucket_seconds: f32,
+    bucket_start: Option<f32>,
+    bucket: Vec<T>,
+    fns: SampleFns<T>,
+}
+
+impl<T: Sample> TimeBucketMethod<T> {
+    pub fn new(bucket_seconds: f32) -> TimeBucketMethod<T> {
+        TimeBucketMethod {
+            bucket_seconds,
+            bucket_start: None,
+            bucket: Vec::new(),
+            fns: SampleFns::new(),
+        }
+    }
+}
+
+impl<T> TimeBucketMethod<T> {
+    fn persist(&mut self, value: T, mut store: impl FnMut(T)) {
+        let time = (self.fns.time)(&value);
+        let bucket_start = *self.bucket_start.get_or_insert(time);
+        if time >= bucket_start + self.bucket_seconds {
+            if !self.bucket.is_empty() {
+                store(self.fns.mean(&self.bucket));
+                self.bucket.clear();
+            }
+            // Skip straight over any empty buckets
+            let buckets_passed = ((time - bucket_start) / self.bucket_seconds).floor();
+            self.bucket_start = Some(bucket_start + buckets_passed * self.bucket_seconds);
+        }
+        self.bucket.push(value);
+    }
+}
+
+/// Largest-triangle-three-buckets: from each bucket of [n] samples keep the one which makes
+/// the largest triangle with the previously kept sample and the mean of the next bucket. This
+/// keeps the visual shape of a line plot far better than picking by position.
+#[derive(Debug, Clone)]
+pub struct LttbMethod<T> {
+    n: usize,
+    previous: Option<T>,
+    bucket: Vec<T>,
+    next_bucket: Vec<T>,
+    fns: SampleFns<T>,
+}
+
+impl<T: Sample> LttbMethod<T> {
+    pub fn new(n: usize) -> LttbMethod<T> {
+        LttbMethod {
+            n,
+            previous: None,
+            bucket: Vec::with_capacity(n),
+            next_bucket: Vec::with_capacity(n),
+            fns: SampleFns::new(),
+        }
+    }
+}
+
+impl<T: Clone> LttbMethod<T> {
+    fn persist(&mut self, value: T, mut store: impl FnMut(T)) {
+        // The very first sample is always kept, as the first point of the line
+        let Some(previous) = &self.previous else {
+            store(value.clone());
+            self.previous = Some(value);
+            return;
+        };
+        if self.bucket.len() < self.n {
+            self.bucket.push(value);
+            return;
+        }
+        self.next_bucket.push(value);
+        if self.next_bucket.len() < self.n {
+            return;
+        }
+
+        let (time, val) = (self.fns.time, self.fns.value);
+        let next_mean = self.fns.mean(&self.next_bucket);
+        let (ax, ay) = (time(previous), val(previous));
+        let (cx, cy) = (time(&next_mean), val(&next_mean));
+        let triangle_area = |b: &T| {
+            let (bx, by) = (time(b), val(b));
+            ((ax - cx) * (by - ay) - (ax - bx) * (cy - ay)).abs()
+        };
+        if let Some(selected) = self
+            .bucket
+            .iter()
+            .max_by(|a, b| triangle_area(a).total_cmp(&triangle_area(b)))
+            .cloned()
+        {
+            store(selected.clone());
+            self.previous = Some(selected);
+        }
+        std::mem::swap(&mut self.bucket, &mut self.next_bucket);
+        self.next_bucket.clear();
+    }
+}
+
+#[cfg(test)]
 mod test {
     use super::*;
 
@@ -201,4 +432,97 @@ mod test {
         assert_eq!(history_store.primary_ringbuf.flatten_copy(), vec![8, 9, 10]);
         assert_eq!(history_store.storage_ringbuf.flatten_copy(), vec![3, 6]);
     }
+
+    #[test]
+    fn test_iter_storage_and_merged() {
+        let mut history_store: HistoryStore<u8> = HistoryStore::new(3, 2, 2);
+        assert_eq!(history_store.iter_merged().count(), 0);
+        for i in 1..=10 {
+            history_store.push(i);
+        }
+        let storage: Vec<u8> = history_store.iter_storage().cloned().collect();
+        assert_eq!(storage, vec![4, 6]);
+        let merged: Vec<u8> = history_store.iter_merged().cloned().collect();
+        assert_eq!(merged, vec![4, 6, 8, 9, 10]);
+    }
+
+    fn persist_all(
+        mut method: PersistenceMethod<(f32, f32)>,
+        samples: &[(f32, f32)],
+    ) -> Vec<(f32, f32)> {
+        let mut stored = vec![];
+        for sample in samples {
+            method.persist(*sample, |persisted| stored.push(persisted));
+        }
+        stored
+    }
+
+    #[test]
+    fn test_min_max_mean() {
+        let samples = [(0.0, 1.0), (1.0, 9.0), (2.0, -4.0), (3.0, 2.0), (4.0, 5.0)];
+        let stored = persist_all(
+            PersistenceMethod::MinMaxMean(MinMaxMeanMethod::new(4)),
+            &samples,
+        );
+        // The spike at 9.0 survives, and the trailing partial bucket waits
+        assert_eq!(stored, vec![(1.0, 9.0), (1.5, 2.0), (2.0, -4.0)]);
+    }
+
+    #[test]
+    fn test_time_bucket() {
+        let samples = [
+            (0.0, 1.0),
+            (0.5, 3.0),
+            (1.0, 10.0),
+            (3.5, 4.0),
+            (3.9, 6.0),
+            (4.0, 0.0),
+        ];
+        let stored = persist_all(
+            PersistenceMethod::TimeBucket(TimeBucketMethod::new(1.0)),
+            &samples,
+        );
+        assert_eq!(stored, vec![(0.25, 2.0), (1.0, 10.0), (3.7, 5.0)]);
+    }
+
+    #[test]
+    fn test_lttb() {
+        let samples = [
+            (0.0, 0.0),
+            // First bucket, the peak makes the biggest triangle
+            (1.0, 0.0),
+            (2.0, 5.0),
+            (3.0, 0.0),
+            // Second bucket, only used for its mean until a third arrives
+            (4.0, 0.0),
+            (5.0, 0.0),
+            (6.0, 0.0),
+        ];
+        let stored = persist_all(PersistenceMethod::Lttb(LttbMethod::new(3)), &samples);
+        assert_eq!(stored, vec![(0.0, 0.0), (2.0, 5.0)]);
+    }
+
+    #[test]
+    fn test_storage_with_persistence() {
+        let mut history_store: HistoryStore<(f32, f32)> = HistoryStore::with_persistence(
+            2,
+            10,
+            PersistenceMethod::MinMaxMean(MinMaxMeanMethod::new(2)),
+        );
+        for i in 0..6 {
+            history_store.push((i as f32, (i * i) as f32));
+        }
+        let storage: Vec<(f32, f32)> = history_store.iter_storage().cloned().collect();
+        assert_eq!(
+            storage,
+            vec![
+                (0.0, 0.0),
+                (0.5, 0.5),
+                (1.0, 1.0),
+                (2.0, 4.0),
+                (2.5, 6.5),
+                (3.0, 9.0)
+            ]
+        );
+    }
 }
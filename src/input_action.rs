@@ -1,12 +1,291 @@
-use bevy::prelude::Reflect;
+use crate::storage;
+use bevy::prelude::*;
 use leafwing_input_manager::prelude::*;
+use leafwing_input_manager::user_input::InputKind::Mouse;
+use std::collections::HashMap;
+
+const KEYBINDINGS_PATH: &str = "keybindings.cfg";
 
 #[derive(Actionlike, PartialEq, Eq, Hash, Clone, Copy, Debug, Reflect)]
 pub enum InputAction {
     Up,
     Down,
+    /// Analogue alternative to [Up]/[Down], i.e. a gamepad stick
+    Throttle,
     MouseMove,
     MouseLClick,
     ZoomIn,
     ZoomOut,
+    /// Switch the camera between following the lift and showing the whole building
+    ToggleOverview,
+    TogglePauseTime,
+    SpeedUp,
+    SlowDown,
+    Pause,
+    ExportSession,
+    /// Show or hide the debug tools, when built with them
+    ToggleDebugUi,
+    /// Show or hide the developer console
+    ToggleConsole,
+}
+
+impl InputAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Up => "Up",
+            Self::Down => "Down",
+            Self::Throttle => "Throttle",
+            Self::MouseMove => "Mouse Move",
+            Self::MouseLClick => "Select",
+            Self::ZoomIn => "Zoom In",
+            Self::ZoomOut => "Zoom Out",
+            Self::ToggleOverview => "Overview",
+            Self::TogglePauseTime => "Pause Time",
+            Self::SpeedUp => "Speed Up",
+            Self::SlowDown => "Slow Down",
+            Self::Pause => "Pause Menu",
+            Self::ExportSession => "Export Session",
+            Self::ToggleDebugUi => "Debug Windows",
+            Self::ToggleConsole => "Console",
+        }
+    }
+}
+
+/// The actions which can be bound to a key, in the order they're shown in settings
+pub const REBINDABLE_ACTIONS: [InputAction; 12] = [
+    InputAction::Up,
+    InputAction::Down,
+    InputAction::ZoomIn,
+    InputAction::ZoomOut,
+    InputAction::ToggleOverview,
+    InputAction::TogglePauseTime,
+    InputAction::SpeedUp,
+    InputAction::SlowDown,
+    InputAction::Pause,
+    InputAction::ExportSession,
+    InputAction::ToggleDebugUi,
+    InputAction::ToggleConsole,
+];
+
+/// Keys which are allowed to be bound, also used to read them back from the saved file
+const BINDABLE_KEYS: [KeyCode; 71] = [
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+    KeyCode::Space,
+    KeyCode::Enter,
+    KeyCode::Tab,
+    KeyCode::Backspace,
+    KeyCode::Escape,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Semicolon,
+    KeyCode::Quote,
+    KeyCode::BracketLeft,
+    KeyCode::BracketRight,
+    KeyCode::Minus,
+    KeyCode::Equal,
+    KeyCode::Backquote,
+    KeyCode::ShiftLeft,
+    KeyCode::ShiftRight,
+    KeyCode::ControlLeft,
+    KeyCode::ControlRight,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+];
+
+pub fn is_bindable(key: &KeyCode) -> bool {
+    BINDABLE_KEYS.contains(key)
+}
+
+pub fn key_name(key: &KeyCode) -> String {
+    format!("{:?}", key)
+}
+
+fn key_of_name(name: &str) -> Option<KeyCode> {
+    BINDABLE_KEYS.into_iter().find(|key| key_name(key) == name)
+}
+
+fn action_of_name(name: &str) -> Option<InputAction> {
+    REBINDABLE_ACTIONS
+        .into_iter()
+        .find(|action| format!("{:?}", action) == name)
+}
+
+/// The player's choice of key for each of [REBINDABLE_ACTIONS], saved to [KEYBINDINGS_PATH]
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct Keybindings {
+    keys: HashMap<InputAction, KeyCode>,
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        let keys = HashMap::from([
+            (InputAction::Up, KeyCode::KeyW),
+            (InputAction::Down, KeyCode::KeyS),
+            (InputAction::ZoomIn, KeyCode::KeyQ),
+            (InputAction::ZoomOut, KeyCode::KeyE),
+            (InputAction::ToggleOverview, KeyCode::Tab),
+            (InputAction::TogglePauseTime, KeyCode::KeyP),
+            (InputAction::SpeedUp, KeyCode::Period),
+            (InputAction::SlowDown, KeyCode::Comma),
+            (InputAction::Pause, KeyCode::Escape),
+            (InputAction::ExportSession, KeyCode::F9),
+            (InputAction::ToggleDebugUi, KeyCode::F3),
+            (InputAction::ToggleConsole, KeyCode::Backquote),
+        ]);
+        Self { keys }
+    }
+}
+
+impl Keybindings {
+    pub fn key(&self, action: &InputAction) -> Option<KeyCode> {
+        self.keys.get(action).copied()
+    }
+
+    /// Bind [key] to [action], any action which already had [key] takes [action]'s old key
+    /// instead, so nothing ends up doubly bound or unbound
+    pub fn rebind(&mut self, action: InputAction, key: KeyCode) {
+        let old_key = self.keys.insert(action, key);
+        let clashing = self
+            .keys
+            .iter()
+            .find(|(other_action, other_key)| **other_action != action && **other_key == key)
+            .map(|(other_action, _)| *other_action);
+        if let Some(clashing) = clashing {
+            match old_key {
+                Some(old_key) => self.keys.insert(clashing, old_key),
+                None => self.keys.remove(&clashing),
+            };
+        }
+    }
+
+    pub fn input_map(&self) -> InputMap<InputAction> {
+        let mut input_map = InputMap::default();
+        for (action, key) in self.keys.iter() {
+            input_map.insert(*action, *key);
+        }
+        input_map.insert(InputAction::MouseMove, SingleAxis::mouse_motion_y());
+        input_map.insert(InputAction::MouseLClick, Mouse(MouseButton::Left));
+        // Gamepad bindings aren't rebindable (yet)
+        input_map.insert(InputAction::Up, GamepadButtonType::RightTrigger2);
+        input_map.insert(InputAction::Down, GamepadButtonType::LeftTrigger2);
+        input_map.insert(
+            InputAction::Throttle,
+            SingleAxis::symmetric(GamepadAxisType::LeftStickY, 0.1),
+        );
+        input_map.insert(InputAction::ZoomIn, GamepadButtonType::RightTrigger);
+        input_map.insert(InputAction::ZoomOut, GamepadButtonType::LeftTrigger);
+        input_map.insert(InputAction::ToggleOverview, GamepadButtonType::North);
+        input_map.insert(InputAction::TogglePauseTime, GamepadButtonType::Select);
+        input_map.insert(InputAction::Pause, GamepadButtonType::Start);
+        input_map
+    }
+
+    fn to_cfg(&self) -> String {
+        REBINDABLE_ACTIONS
+            .iter()
+            .filter_map(|action| {
+                self.key(action)
+                    .map(|key| format!("{:?}={}\n", action, key_name(&key)))
+            })
+            .collect()
+    }
+
+    /// Unknown lines are skipped, so a partially valid file still keeps what it can
+    fn of_cfg(cfg: &str) -> Self {
+        let mut keybindings = Self::default();
+        for line in cfg.lines() {
+            let Some((action, key)) = line.split_once('=') else {
+                continue;
+            };
+            match (action_of_name(action.trim()), key_of_name(key.trim())) {
+                (Some(action), Some(key)) => keybindings.rebind(action, key),
+                _ => warn!("Ignoring unknown keybinding: {}", line),
+            }
+        }
+        keybindings
+    }
+
+    pub fn load_or_default() -> Self {
+        match storage::read_to_string(KEYBINDINGS_PATH) {
+            Ok(cfg) => Self::of_cfg(&cfg),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) {
+        if let Err(error) = storage::write(KEYBINDINGS_PATH, &self.to_cfg()) {
+            warn!("Failed to save keybindings: {:?}", error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rebind_swaps_clashes() {
+        let mut keybindings = Keybindings::default();
+        keybindings.rebind(InputAction::Up, KeyCode::KeyS);
+        assert_eq!(keybindings.key(&InputAction::Up), Some(KeyCode::KeyS));
+        assert_eq!(keybindings.key(&InputAction::Down), Some(KeyCode::KeyW));
+    }
+
+    #[test]
+    fn test_cfg_roundtrip() {
+        let mut keybindings = Keybindings::default();
+        keybindings.rebind(InputAction::Pause, KeyCode::KeyX);
+        keybindings.rebind(InputAction::ZoomIn, KeyCode::ArrowUp);
+        assert_eq!(Keybindings::of_cfg(&keybindings.to_cfg()), keybindings);
+    }
 }
@@ -10,6 +10,9 @@ mod history_store;
 mod input_action;
 mod loading;
 mod menu;
+mod scenario_editor;
+mod settings;
+mod storage;
 mod ui_widgets;
 
 use crate::audio::InternalAudioPlugin;
@@ -21,13 +24,14 @@ use crate::camera::CameraPlugin;
 use crate::game::CoreGamePlugin;
 use crate::input_action::InputAction;
 use bevy::app::App;
-#[cfg(debug_assertions)]
+#[cfg(feature = "frame-time-diagnostics")]
 use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
 use bevy::prelude::*;
 use bevy_ecs_tilemap::TilemapPlugin;
-use bevy_inspector_egui::bevy_egui::EguiPlugin;
-use bevy_inspector_egui::quick::WorldInspectorPlugin;
+use bevy_egui::EguiPlugin;
 
+#[cfg(feature = "debug-tools")]
+pub use crate::game::debug::console::add_console_layer;
 pub use crate::game::spawn_simulation::oneshot_simulation;
 
 // This example game uses States to separate logic
@@ -40,27 +44,48 @@ enum GameState {
     PlayingDay,
     MainMenu,
     PlayingMenu,
+    /// Passed through on the way back into [PlayingDay], so the day is torn down and rebuilt
+    RestartingDay,
+    /// An endless run has ended, see [game::endless]
+    GameOver,
+    ScenarioEditor,
+}
+
+/// Only meaningful during [GameState::PlayingDay]
+#[derive(States, Default, Clone, Eq, PartialEq, Debug, Hash)]
+enum PauseState {
+    #[default]
+    Running,
+    Paused,
 }
 
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
-        app.init_state::<GameState>().add_plugins((
-            LoadingPlugin,
-            MenuPlugin,
-            CoreGamePlugin,
-            InternalAudioPlugin,
-            CameraPlugin,
-            core::CorePlugin,
-            game_menu::GameMenuPlugin,
-        ));
+        app.init_state::<GameState>()
+            .init_state::<PauseState>()
+            .add_plugins((
+                LoadingPlugin,
+                MenuPlugin,
+                CoreGamePlugin,
+                InternalAudioPlugin,
+                CameraPlugin,
+                core::CorePlugin,
+                game_menu::GameMenuPlugin,
+                settings::SettingsPlugin,
+                ui_widgets::UiWidgetsPlugin,
+                scenario_editor::ScenarioEditorPlugin,
+            ));
         app.add_plugins((
             InputManagerPlugin::<InputAction>::default(),
             TilemapPlugin,
             EguiPlugin,
-            WorldInspectorPlugin::new(),
         ));
+        #[cfg(feature = "debug-tools")]
+        {
+            app.add_plugins(game::debug::DebugPlugin);
+        }
         #[cfg(feature = "frame-time-diagnostics")]
         {
             app.add_plugins((FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin::default()));
@@ -2,11 +2,14 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use bevy::asset::AssetMetaCheck;
+use bevy::log::LogPlugin;
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 use bevy::winit::WinitWindows;
 use bevy::DefaultPlugins;
 use bevy_egui::EguiPlugin;
+#[cfg(feature = "debug-tools")]
+use going_up::add_console_layer;
 use going_up::GamePlugin;
 use std::io::Cursor;
 use winit::window::Icon;
@@ -29,7 +32,17 @@ fn main() {
                     }),
                     ..default()
                 })
-                .set(ImagePlugin::default_nearest()),
+                .set(ImagePlugin::default_nearest())
+                .set(LogPlugin {
+                    // Let all of the game's own logs through to the in-game console, which has
+                    // its own level filter
+                    #[cfg(feature = "debug-tools")]
+                    filter: "wgpu=error,naga=warn,going_up=trace".to_string(),
+                    // Also show logs in the in-game console
+                    #[cfg(feature = "debug-tools")]
+                    update_subscriber: Some(add_console_layer),
+                    ..default()
+                }),
         )
         .add_plugins(GamePlugin)
         .add_systems(Startup, set_window_icon)
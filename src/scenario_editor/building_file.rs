@@ -0,0 +1,381 @@
+//! Buildings made in the [super] editor, saved as plain text so they can be diffed and tweaked
+//! by hand. A file lists the floors from the bottom up, each floor's hours given as ranges:
+//!
+//! ```text
+//! floor lobby
+//! activity 0..10 both 1 3
+//! activity 10..24 sink
+//! strength 0..24 5
+//! ```
+//!
+//! Activities are `sink`, `source`, `random` or `both <sink weight> <source weight>`. The ranges
+//! of each floor must cover the day exactly once, as checked by [RawFloorConfig::new].
+
+use crate::game::spawn_simulation::{FloorConfigError, RawFloorConfig, SinkOrSource};
+use crate::game::world_gen::{Class, FloorKind, Level, LevelKind};
+use crate::storage;
+use std::fmt::Formatter;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+pub const BUILDINGS_DIR: &str = "buildings";
+const BUILDING_EXTENSION: &str = "building";
+
+/// One floor of a [BuildingDesign], kept per hour so it can be painted on a timeline
+#[derive(Debug, Clone, PartialEq)]
+pub struct FloorDesign {
+    pub kind: FloorKind,
+    pub sink_or_source: [SinkOrSource; 24],
+    pub strength: [usize; 24],
+}
+
+impl FloorDesign {
+    /// The usual hours of a [kind] floor at [floor_num], see [FloorKind::prefab_config]
+    pub fn of_prefab(floor_num: i32, kind: FloorKind) -> Self {
+        Self::of_config(kind, &kind.prefab_config(floor_num))
+    }
+
+    fn of_config(kind: FloorKind, config: &RawFloorConfig) -> Self {
+        Self {
+            kind,
+            sink_or_source: *config.sink_or_source(),
+            strength: *config.strength(),
+        }
+    }
+
+    pub fn to_config(&self) -> Result<RawFloorConfig, FloorConfigError> {
+        RawFloorConfig::new(runs(&self.sink_or_source), runs(&self.strength))
+    }
+}
+
+/// A building's floors, from the bottom up
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuildingDesign {
+    pub floors: Vec<FloorDesign>,
+}
+
+impl Default for BuildingDesign {
+    fn default() -> Self {
+        let floors = (1..=5)
+            .map(|floor_num| {
+                FloorDesign::of_prefab(floor_num, FloorKind::usual_of_floor_num(floor_num))
+            })
+            .collect();
+        Self { floors }
+    }
+}
+
+#[derive(Debug)]
+pub enum BuildingFileError {
+    Io(std::io::Error),
+    /// A line, counting from 1, which couldn't be read
+    Parse(usize, String),
+    /// A floor, counting from 0 at the bottom, whose hours don't add up
+    Floor(usize, FloorConfigError),
+    NoFloors,
+    /// A name which can't be saved under, see [is_safe_name]
+    BadName(String),
+}
+
+impl std::fmt::Display for BuildingFileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "BuildingFileError({})", error),
+            Self::Parse(line, reason) => {
+                write!(f, "BuildingFileError(line {}: {})", line, reason)
+            }
+            Self::Floor(index, error) => {
+                write!(f, "BuildingFileError(floor {}: {})", index, error)
+            }
+            Self::NoFloors => write!(f, "BuildingFileError(no floors)"),
+            Self::BadName(name) => write!(
+                f,
+                "BuildingFileError(bad name {:?}, use letters, digits, spaces, - and _)",
+                name
+            ),
+        }
+    }
+}
+
+impl From<std::io::Error> for BuildingFileError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// Runs of equal values, as the ranges [RawFloorConfig::new] takes
+fn runs<T: Copy + PartialEq>(hours: &[T; 24]) -> Vec<(Range<u8>, T)> {
+    let mut runs: Vec<(Range<u8>, T)> = vec![];
+    for (hour, value) in hours.iter().enumerate() {
+        match runs.last_mut() {
+            Some((range, last)) if last == value => range.end = hour as u8 + 1,
+            _ => runs.push((hour as u8..hour as u8 + 1, *value)),
+        }
+    }
+    runs
+}
+
+fn sink_or_source_to_str(sink_or_source: &SinkOrSource) -> String {
+    match sink_or_source {
+        SinkOrSource::Sink => "sink".to_string(),
+        SinkOrSource::Source => "source".to_string(),
+        SinkOrSource::Random => "random".to_string(),
+        SinkOrSource::Both {
+            sink_weight,
+            source_weight,
+        } => format!("both {} {}", sink_weight, source_weight),
+    }
+}
+
+fn sink_or_source_of_words(words: &[&str]) -> Option<SinkOrSource> {
+    match words {
+        ["sink"] => Some(SinkOrSource::Sink),
+        ["source"] => Some(SinkOrSource::Source),
+        ["random"] => Some(SinkOrSource::Random),
+        ["both", sink_weight, source_weight] => Some(SinkOrSource::Both {
+            sink_weight: sink_weight.parse().ok()?,
+            source_weight: source_weight.parse().ok()?,
+        }),
+        _ => None,
+    }
+}
+
+fn floor_kind_of_str(name: &str) -> Option<FloorKind> {
+    FloorKind::ALL
+        .into_iter()
+        .find(|kind| kind.name().eq_ignore_ascii_case(name))
+}
+
+fn range_of_str(range: &str) -> Option<Range<u8>> {
+    let (start, end) = range.split_once("..")?;
+    let range = start.parse().ok()?..end.parse().ok()?;
+    (range.start < range.end && range.end <= 24).then_some(range)
+}
+
+/// Names are used as file stems in [BUILDINGS_DIR], so can't have separators, dots etc. that
+/// would put them anywhere else
+fn is_safe_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, ' ' | '-' | '_'))
+}
+
+/// A floor part way through being read, checked once all its lines are in
+struct FloorRanges {
+    kind: FloorKind,
+    sink_or_source: Vec<(Range<u8>, SinkOrSource)>,
+    strength: Vec<(Range<u8>, usize)>,
+}
+
+impl BuildingDesign {
+    /// The floors as [RawFloorConfig]s, or the first floor which doesn't validate
+    pub fn to_configs(&self) -> Result<Vec<(FloorKind, RawFloorConfig)>, BuildingFileError> {
+        if self.floors.is_empty() {
+            return Err(BuildingFileError::NoFloors);
+        }
+        self.floors
+            .iter()
+            .enumerate()
+            .map(|(i, floor)| {
+                floor
+                    .to_config()
+                    .map(|config| (floor.kind, config))
+                    .map_err(|error| BuildingFileError::Floor(i, error))
+            })
+            .collect()
+    }
+
+    pub fn to_level(&self) -> Result<Level, BuildingFileError> {
+        Ok(Level::with_floors(
+            LevelKind::Hotel,
+            Class::One,
+            self.to_configs()?,
+        ))
+    }
+
+    /// Hours of the day in which nobody can spawn, as there are no sources, or nowhere to go, as
+    /// there are no sinks. [SinkOrSource::Random] and [SinkOrSource::Both] count as either
+    pub fn dead_hours(&self) -> Vec<u8> {
+        (0..24u8)
+            .filter(|hour| {
+                let active = || {
+                    self.floors
+                        .iter()
+                        .filter(|floor| floor.strength[*hour as usize] > 0)
+                        .map(|floor| floor.sink_or_source[*hour as usize])
+                };
+                let has_sink = active().any(|activity| activity != SinkOrSource::Source);
+                let has_source = active().any(|activity| activity != SinkOrSource::Sink);
+                !has_sink || !has_source
+            })
+            .collect()
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for floor in self.floors.iter() {
+            text.push_str(&format!("floor {}\n", floor.kind.name().to_lowercase()));
+            for (range, sink_or_source) in runs(&floor.sink_or_source) {
+                text.push_str(&format!(
+                    "activity {}..{} {}\n",
+                    range.start,
+                    range.end,
+                    sink_or_source_to_str(&sink_or_source)
+                ));
+            }
+            for (range, strength) in runs(&floor.strength) {
+                text.push_str(&format!(
+                    "strength {}..{} {}\n",
+                    range.start, range.end, strength
+                ));
+            }
+        }
+        text
+    }
+
+    /// Blank lines and lines starting with '#' are skipped
+    pub fn of_text(text: &str) -> Result<Self, BuildingFileError> {
+        let mut floors: Vec<FloorRanges> = vec![];
+        for (i, line) in text.lines().enumerate() {
+            let line_num = i + 1;
+            let parse_error = |reason: &str| BuildingFileError::Parse(line_num, reason.to_string());
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                [] => {}
+                [comment, ..] if comment.starts_with('#') => {}
+                ["floor", kind] => floors.push(FloorRanges {
+                    kind: floor_kind_of_str(kind)
+                        .ok_or_else(|| parse_error("unknown floor kind"))?,
+                    sink_or_source: vec![],
+                    strength: vec![],
+                }),
+                ["activity", range, activity @ ..] => {
+                    let floor = floors
+                        .last_mut()
+                        .ok_or_else(|| parse_error("activity before any floor"))?;
+                    let range = range_of_str(range).ok_or_else(|| parse_error("bad hours"))?;
+                    let activity = sink_or_source_of_words(activity)
+                        .ok_or_else(|| parse_error("unknown activity"))?;
+                    floor.sink_or_source.push((range, activity));
+                }
+                ["strength", range, strength] => {
+                    let floor = floors
+                        .last_mut()
+                        .ok_or_else(|| parse_error("strength before any floor"))?;
+                    let range = range_of_str(range).ok_or_else(|| parse_error("bad hours"))?;
+                    let strength = strength.parse().map_err(|_| parse_error("bad strength"))?;
+                    floor.strength.push((range, strength));
+                }
+                _ => return Err(parse_error("unknown line")),
+            }
+        }
+        if floors.is_empty() {
+            return Err(BuildingFileError::NoFloors);
+        }
+        let floors = floors
+            .into_iter()
+            .enumerate()
+            .map(|(i, floor)| {
+                RawFloorConfig::new(floor.sink_or_source, floor.strength)
+                    .map(|config| FloorDesign::of_config(floor.kind, &config))
+                    .map_err(|error| BuildingFileError::Floor(i, error))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { floors })
+    }
+
+    /// Saved as [name] in [BUILDINGS_DIR], only once it validates
+    pub fn save(&self, name: &str) -> Result<PathBuf, BuildingFileError> {
+        if !is_safe_name(name) {
+            return Err(BuildingFileError::BadName(name.to_string()));
+        }
+        self.to_configs()?;
+        let path = Path::new(BUILDINGS_DIR).join(format!("{}.{}", name, BUILDING_EXTENSION));
+        storage::write(&path, &self.to_text())?;
+        Ok(path)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, BuildingFileError> {
+        Self::of_text(&storage::read_to_string(path)?)
+    }
+
+    /// Building files in [BUILDINGS_DIR], sorted by name
+    pub fn list_saved() -> Vec<PathBuf> {
+        storage::list(BUILDINGS_DIR, BUILDING_EXTENSION)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runs() {
+        let mut hours = [1; 24];
+        hours[5..9].fill(2);
+        assert_eq!(runs(&hours), vec![(0..5, 1), (5..9, 2), (9..24, 1)]);
+    }
+
+    #[test]
+    fn test_text_roundtrip() {
+        let mut design = BuildingDesign::default();
+        design.floors[2].sink_or_source[3] = SinkOrSource::Both {
+            sink_weight: 2,
+            source_weight: 5,
+        };
+        design.floors[2].strength[20] = 0;
+        assert_eq!(BuildingDesign::of_text(&design.to_text()).unwrap(), design);
+    }
+
+    #[test]
+    fn test_bad_files() {
+        assert!(matches!(
+            BuildingDesign::of_text("# nothing here\n"),
+            Err(BuildingFileError::NoFloors)
+        ));
+        assert!(matches!(
+            BuildingDesign::of_text("floor regular\nactivity 0..24 upwards\n"),
+            Err(BuildingFileError::Parse(2, _))
+        ));
+        assert!(matches!(
+            BuildingDesign::of_text(
+                "floor lobby\nactivity 0..24 sink\nstrength 0..24 1\n\
+                 floor regular\nactivity 0..10 source\nstrength 0..24 1\n"
+            ),
+            Err(BuildingFileError::Floor(1, FloorConfigError::RangeGap(10)))
+        ));
+    }
+
+    #[test]
+    fn test_safe_names() {
+        assert!(is_safe_name("Tower 2_final-v3"));
+        assert!(!is_safe_name(""));
+        assert!(!is_safe_name("../x"));
+        assert!(!is_safe_name("a/b"));
+        assert!(!is_safe_name("C:\\x"));
+        assert!(matches!(
+            BuildingDesign::default().save("../x"),
+            Err(BuildingFileError::BadName(_))
+        ));
+    }
+
+    #[test]
+    fn test_dead_hours() {
+        let mut design = BuildingDesign {
+            floors: vec![
+                FloorDesign::of_prefab(1, FloorKind::Lobby),
+                FloorDesign::of_prefab(2, FloorKind::Regular),
+            ],
+        };
+        for floor in design.floors.iter_mut() {
+            floor.sink_or_source = [SinkOrSource::Source; 24];
+        }
+        design.floors[1].sink_or_source[..12].fill(SinkOrSource::Sink);
+        design.floors[1].strength[..6].fill(0);
+        assert_eq!(
+            design.dead_hours(),
+            (0..6).chain(12..24).collect::<Vec<u8>>()
+        );
+    }
+}
@@ -0,0 +1,481 @@
+//! An editor for designing buildings and who comes and goes from their floors, rather than
+//! hand writing prefabs. Each floor's activity and strength are painted hour by hour on a
+//! timeline, with a heat map of the spawn rates they'd give. Designs are saved as
+//! [building_file]s, and can be played straight from the editor.
+
+mod building_file;
+
+use crate::game::campaign::ActiveCampaignDay;
+use crate::game::endless::EndlessRun;
+use crate::game::game_clock::GameTimeConfig;
+use crate::game::seed::DaySeed;
+use crate::game::spawn_simulation::{hourly_spawn_rates, SinkOrSource};
+use crate::game::world_gen::{FloorKind, Level};
+use crate::game::{floor_num_pretty_str, FloorNum};
+use crate::GameState;
+use bevy::prelude::*;
+use bevy_egui::egui::{Align2, Color32, RichText, Sense};
+use bevy_egui::{egui, EguiContexts};
+use building_file::{BuildingDesign, FloorDesign};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+const CELL_SIZE: egui::Vec2 = egui::vec2(28.0, 22.0);
+const FLOOR_LABEL_WIDTH: f32 = 40.0;
+const MAX_STRENGTH: usize = 50;
+/// [SinkOrSource::Random] floors are rolled with this in the heat map, so it holds still
+const PREVIEW_SEED: u64 = 0;
+
+pub struct ScenarioEditorPlugin;
+
+impl Plugin for ScenarioEditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::ScenarioEditor),
+            ScenarioEditor::list_saved_system,
+        )
+        .add_systems(
+            Update,
+            editor_system.run_if(in_state(GameState::ScenarioEditor)),
+        )
+        .init_resource::<ScenarioEditor>();
+    }
+}
+
+/// Kept while away from the editor, so a design can be played and then carried on with
+#[derive(Resource, Debug)]
+struct ScenarioEditor {
+    design: BuildingDesign,
+    /// Index of the floor being edited, from the bottom
+    selected: usize,
+    name: String,
+    activity_brush: SinkOrSource,
+    /// Weights used when painting [SinkOrSource::Both]
+    both_weights: (usize, usize),
+    strength_brush: usize,
+    /// How the last save, load or play went
+    status: Option<String>,
+    /// The design the heat map was computed for, and its rates
+    preview: Option<(BuildingDesign, BTreeMap<FloorNum, [f32; 24]>)>,
+    /// Building files to load, listed on entering the editor and after saving
+    saved: Vec<PathBuf>,
+}
+
+impl Default for ScenarioEditor {
+    fn default() -> Self {
+        Self {
+            design: BuildingDesign::default(),
+            selected: 0,
+            name: "untitled".to_string(),
+            activity_brush: SinkOrSource::Source,
+            both_weights: (1, 1),
+            strength_brush: 2,
+            status: None,
+            preview: None,
+            saved: vec![],
+        }
+    }
+}
+
+impl ScenarioEditor {
+    fn list_saved_system(mut editor: ResMut<Self>) {
+        editor.saved = BuildingDesign::list_saved();
+    }
+}
+
+/// Floors in a designed building are numbered from 1, as in [crate::game::floors::build_floor_map]
+fn floor_num_of_index(index: usize) -> i32 {
+    index as i32 + 1
+}
+
+fn preview_rates(design: &BuildingDesign) -> BTreeMap<FloorNum, [f32; 24]> {
+    let raw_floors = design
+        .floors
+        .iter()
+        .enumerate()
+        .filter_map(|(i, floor)| {
+            let config = floor.to_config().ok()?;
+            Some((FloorNum(floor_num_of_index(i)), config))
+        })
+        .collect();
+    hourly_spawn_rates(&raw_floors, &mut StdRng::seed_from_u64(PREVIEW_SEED))
+}
+
+fn activity_colour(sink_or_source: &SinkOrSource) -> Color32 {
+    match sink_or_source {
+        SinkOrSource::Sink => Color32::from_rgb(50, 90, 200),
+        SinkOrSource::Source => Color32::from_rgb(40, 160, 70),
+        SinkOrSource::Random => Color32::DARK_GRAY,
+        SinkOrSource::Both { .. } => Color32::from_rgb(140, 70, 170),
+    }
+}
+
+fn activity_text(sink_or_source: &SinkOrSource) -> String {
+    match sink_or_source {
+        SinkOrSource::Sink => "in",
+        SinkOrSource::Source => "out",
+        SinkOrSource::Random => "?",
+        SinkOrSource::Both { .. } => "±",
+    }
+    .to_string()
+}
+
+/// From dark for nobody turning up, to bright orange for the busiest floor and hour
+fn heat_colour(fraction: f32) -> Color32 {
+    let fraction = fraction.clamp(0.0, 1.0);
+    Color32::from_rgb(
+        (40.0 + 215.0 * fraction) as u8,
+        (40.0 + 120.0 * fraction) as u8,
+        40,
+    )
+}
+
+/// The hour each cell's the start of, every few hours
+fn hour_labels(ui: &mut egui::Ui) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(CELL_SIZE.x * 24.0, 14.0), Sense::hover());
+    for hour in (0..24).step_by(3) {
+        ui.painter().text(
+            rect.left_center() + egui::vec2(hour as f32 * CELL_SIZE.x, 0.0),
+            Align2::LEFT_CENTER,
+            format!("{:02}", hour),
+            egui::FontId::monospace(11.0),
+            ui.visuals().text_color(),
+        );
+    }
+}
+
+/// The cell of [rect] under [pos], for rows of 24 hourly cells
+fn hour_at(rect: egui::Rect, pos: egui::Pos2) -> Option<usize> {
+    let hour = ((pos.x - rect.min.x) / CELL_SIZE.x).floor();
+    (0.0..24.0).contains(&hour).then_some(hour as usize)
+}
+
+fn hour_cell(rect: egui::Rect, hour: usize) -> egui::Rect {
+    egui::Rect::from_min_size(
+        rect.min + egui::vec2(hour as f32 * CELL_SIZE.x, 0.0),
+        CELL_SIZE,
+    )
+    .shrink(1.0)
+}
+
+/// A row of a cell per hour, which [brush] is painted on to by clicking or dragging across
+fn timeline<T: Copy + PartialEq>(
+    ui: &mut egui::Ui,
+    hours: &mut [T; 24],
+    brush: T,
+    cell_colour: impl Fn(&T) -> Color32,
+    cell_text: impl Fn(&T) -> String,
+) {
+    let (rect, response) = ui.allocate_exact_size(
+        egui::vec2(CELL_SIZE.x * 24.0, CELL_SIZE.y),
+        Sense::click_and_drag(),
+    );
+    if response.clicked() || response.dragged() {
+        let hour = response
+            .interact_pointer_pos()
+            .and_then(|pos| hour_at(rect, pos));
+        if let Some(hour) = hour {
+            hours[hour] = brush;
+        }
+    }
+    if ui.is_rect_visible(rect) {
+        for (hour, value) in hours.iter().enumerate() {
+            let cell = hour_cell(rect, hour);
+            ui.painter().rect_filled(cell, 2.0, cell_colour(value));
+            ui.painter().text(
+                cell.center(),
+                Align2::CENTER_CENTER,
+                cell_text(value),
+                egui::FontId::monospace(11.0),
+                Color32::WHITE,
+            );
+        }
+    }
+}
+
+/// Spawn rates per floor, top floor first, and hour
+fn heat_map(ui: &mut egui::Ui, rates: &BTreeMap<FloorNum, [f32; 24]>) {
+    let max_rate = rates.values().flatten().copied().fold(0.0f32, f32::max);
+    for (floor_num, floor_rates) in rates.iter().rev() {
+        ui.horizontal(|ui| {
+            ui.add_sized(
+                [FLOOR_LABEL_WIDTH, CELL_SIZE.y],
+                egui::Label::new(floor_num_pretty_str(floor_num.0)),
+            );
+            let (rect, response) =
+                ui.allocate_exact_size(egui::vec2(CELL_SIZE.x * 24.0, CELL_SIZE.y), Sense::hover());
+            for (hour, rate) in floor_rates.iter().enumerate() {
+                let fraction = if max_rate > 0.0 { rate / max_rate } else { 0.0 };
+                ui.painter()
+                    .rect_filled(hour_cell(rect, hour), 0.0, heat_colour(fraction));
+            }
+            if let Some(hour) = response.hover_pos().and_then(|pos| hour_at(rect, pos)) {
+                response.on_hover_text(format!(
+                    "{} {:02}:00, {:.1} people/hour",
+                    floor_num_pretty_str(floor_num.0),
+                    hour,
+                    floor_rates[hour]
+                ));
+            }
+        });
+    }
+    ui.horizontal(|ui| {
+        ui.add_space(FLOOR_LABEL_WIDTH);
+        hour_labels(ui);
+    });
+    ui.label(format!("Busiest: {:.1} people/hour", max_rate));
+}
+
+fn floors_ui(ui: &mut egui::Ui, editor: &mut ScenarioEditor) {
+    ui.heading("Floors");
+    egui::ScrollArea::vertical()
+        .max_height(300.0)
+        .show(ui, |ui| {
+            for (i, floor) in editor.design.floors.iter().enumerate().rev() {
+                let label = format!(
+                    "{} {}",
+                    floor_num_pretty_str(floor_num_of_index(i)),
+                    floor.kind.name()
+                );
+                if ui.selectable_label(editor.selected == i, label).clicked() {
+                    editor.selected = i;
+                }
+            }
+        });
+    ui.horizontal(|ui| {
+        if ui.button("Add Floor").clicked() {
+            let floor_num = floor_num_of_index(editor.design.floors.len());
+            editor
+                .design
+                .floors
+                .push(FloorDesign::of_prefab(floor_num, FloorKind::Regular));
+            editor.selected = editor.design.floors.len() - 1;
+        }
+        if ui
+            .add_enabled(
+                editor.design.floors.len() > 1,
+                egui::Button::new("Remove Floor"),
+            )
+            .clicked()
+        {
+            editor.design.floors.remove(editor.selected);
+            editor.selected = editor.selected.min(editor.design.floors.len() - 1);
+        }
+    });
+}
+
+fn file_ui(ui: &mut egui::Ui, editor: &mut ScenarioEditor) {
+    ui.heading("Building");
+    ui.horizontal(|ui| {
+        ui.label("Name");
+        ui.text_edit_singleline(&mut editor.name);
+    });
+    ui.horizontal(|ui| {
+        if ui.button("New").clicked() {
+            *editor = ScenarioEditor {
+                name: editor.name.clone(),
+                saved: std::mem::take(&mut editor.saved),
+                ..default()
+            };
+        }
+        let name = editor.name.trim();
+        if ui
+            .add_enabled(!name.is_empty(), egui::Button::new("Save"))
+            .clicked()
+        {
+            editor.status = Some(match editor.design.save(name) {
+                Ok(path) => {
+                    info!("Saved building to {}", path.display());
+                    editor.saved = BuildingDesign::list_saved();
+                    format!("Saved to {}", path.display())
+                }
+                Err(error) => error.to_string(),
+            });
+        }
+    });
+    if !editor.saved.is_empty() {
+        ui.collapsing("Load", |ui| {
+            let mut loaded = None;
+            for path in editor.saved.iter() {
+                let name = path
+                    .file_stem()
+                    .map_or(String::new(), |name| name.to_string_lossy().to_string());
+                if ui.button(&name).clicked() {
+                    loaded = Some((name, BuildingDesign::load(path)));
+                }
+            }
+            if let Some((name, result)) = loaded {
+                match result {
+                    Ok(design) => {
+                        editor.design = design;
+                        editor.selected = 0;
+                        editor.status = Some(format!("Loaded {}", name));
+                        editor.name = name;
+                    }
+                    Err(error) => {
+                        warn!("Failed to load {}: {}", name, error);
+                        editor.status = Some(error.to_string());
+                    }
+                }
+                editor.saved = BuildingDesign::list_saved();
+            }
+        });
+    }
+    if let Some(status) = &editor.status {
+        ui.label(status);
+    }
+}
+
+fn floor_editor_ui(ui: &mut egui::Ui, editor: &mut ScenarioEditor) {
+    let both = SinkOrSource::Both {
+        sink_weight: editor.both_weights.0,
+        source_weight: editor.both_weights.1,
+    };
+    ui.horizontal(|ui| {
+        ui.label("Activity");
+        for (activity, name) in [
+            (SinkOrSource::Sink, "Sink (in)"),
+            (SinkOrSource::Source, "Source (out)"),
+            (SinkOrSource::Random, "Random (?)"),
+            (both, "Both (±)"),
+        ] {
+            ui.radio_value(&mut editor.activity_brush, activity, name);
+        }
+        ui.add(
+            egui::DragValue::new(&mut editor.both_weights.0)
+                .clamp_range(0..=10)
+                .prefix("in "),
+        );
+        ui.add(
+            egui::DragValue::new(&mut editor.both_weights.1)
+                .clamp_range(0..=10)
+                .prefix("out "),
+        );
+    });
+    if let SinkOrSource::Both { .. } = editor.activity_brush {
+        editor.activity_brush = SinkOrSource::Both {
+            sink_weight: editor.both_weights.0,
+            source_weight: editor.both_weights.1,
+        };
+    }
+    ui.horizontal(|ui| {
+        ui.label("Strength");
+        ui.add(egui::DragValue::new(&mut editor.strength_brush).clamp_range(0..=MAX_STRENGTH));
+    });
+
+    let selected = editor.selected;
+    let (activity_brush, strength_brush) = (editor.activity_brush, editor.strength_brush);
+    let Some(floor) = editor.design.floors.get_mut(selected) else {
+        return;
+    };
+    ui.separator();
+    ui.horizontal(|ui| {
+        let floor_num = floor_num_of_index(selected);
+        ui.heading(floor_num_pretty_str(floor_num));
+        let mut kind = floor.kind;
+        egui::ComboBox::from_label("Kind")
+            .selected_text(kind.name())
+            .show_ui(ui, |ui| {
+                for other_kind in FloorKind::ALL {
+                    ui.selectable_value(&mut kind, other_kind, other_kind.name());
+                }
+            })
+            .response
+            .on_hover_text("Changing kind fills the floor's hours with that kind's usual traffic");
+        if kind != floor.kind {
+            *floor = FloorDesign::of_prefab(floor_num, kind);
+        }
+    });
+    egui::Grid::new("floor timelines")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("");
+            hour_labels(ui);
+            ui.end_row();
+            ui.label("Activity");
+            timeline(
+                ui,
+                &mut floor.sink_or_source,
+                activity_brush,
+                activity_colour,
+                activity_text,
+            );
+            ui.end_row();
+            ui.label("Strength");
+            timeline(
+                ui,
+                &mut floor.strength,
+                strength_brush,
+                |strength| heat_colour(*strength as f32 / MAX_STRENGTH as f32),
+                |strength| strength.to_string(),
+            );
+            ui.end_row();
+        });
+}
+
+fn editor_system(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut editor: ResMut<ScenarioEditor>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut day_seed: ResMut<DaySeed>,
+    mut time_config: ResMut<GameTimeConfig>,
+    mut level: ResMut<Level>,
+) {
+    let editor = &mut *editor;
+    if editor
+        .preview
+        .as_ref()
+        .map_or(true, |(design, _)| *design != editor.design)
+    {
+        editor.preview = Some((editor.design.clone(), preview_rates(&editor.design)));
+    }
+    let ctx = contexts.ctx_mut();
+
+    egui::SidePanel::left("scenario editor").show(ctx, |ui| {
+        file_ui(ui, editor);
+        ui.separator();
+        floors_ui(ui, editor);
+        ui.separator();
+        let dead_hours = editor.design.dead_hours();
+        if !dead_hours.is_empty() {
+            let hours: Vec<String> = dead_hours.iter().map(|hour| hour.to_string()).collect();
+            ui.label(
+                RichText::new(format!(
+                    "Nobody can come or go at hours {}, there needs to be a sink and a source",
+                    hours.join(", ")
+                ))
+                .color(Color32::YELLOW),
+            );
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Play").clicked() {
+                match editor.design.to_level() {
+                    Ok(designed) => {
+                        *level = designed;
+                        *day_seed = DaySeed::default();
+                        *time_config = GameTimeConfig::default();
+                        commands.remove_resource::<ActiveCampaignDay>();
+                        commands.remove_resource::<EndlessRun>();
+                        info!("Playing designed building {}", editor.name);
+                        next_state.set(GameState::PlayingDay);
+                    }
+                    Err(error) => editor.status = Some(error.to_string()),
+                }
+            }
+            if ui.button("Back to Menu").clicked() {
+                next_state.set(GameState::PlayingMenu);
+            }
+        });
+    });
+    egui::CentralPanel::default().show(ctx, |ui| {
+        floor_editor_ui(ui, editor);
+        ui.separator();
+        ui.heading("Spawn rates");
+        if let Some((_, rates)) = &editor.preview {
+            heat_map(ui, rates);
+        }
+    });
+}
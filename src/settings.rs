@@ -0,0 +1,223 @@
+use crate::game::response_curve::{DialResponse, ResponseCurve};
+use crate::input_action::{self, InputAction, Keybindings, REBINDABLE_ACTIONS};
+use crate::ui_widgets::BarOrientation;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use bevy_kira_audio::prelude::*;
+use egui_plot::{Line, Plot, PlotPoints};
+use leafwing_input_manager::prelude::*;
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                settings_window_system,
+                apply_volume_system,
+                capture_rebind_system,
+                apply_keybindings_system,
+            ),
+        )
+        .init_resource::<Settings>()
+        .init_resource::<SettingsWindow>()
+        .init_resource::<Rebinding>()
+        .insert_resource(Keybindings::load_or_default())
+        .register_type::<Settings>();
+    }
+}
+
+#[derive(Resource, Debug, Reflect)]
+pub struct Settings {
+    pub master_volume: f64,
+    pub dial: DialResponse,
+    /// Clicking a floor drives the lift there automatically
+    pub assisted_driving: bool,
+    /// Race a ghost of the best saved run of the same day
+    pub show_ghost: bool,
+    /// Multiplier on top of the window based [crate::core::LayoutScale]
+    pub ui_scale: f32,
+    /// Which way the passengers' patience bars fill
+    pub patience_bar_orientation: BarOrientation,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.0,
+            dial: DialResponse::default(),
+            assisted_driving: false,
+            show_ghost: false,
+            ui_scale: 1.0,
+            patience_bar_orientation: BarOrientation::default(),
+        }
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct SettingsWindow {
+    pub open: bool,
+}
+
+/// The action waiting for a key press to be bound to it, if any
+#[derive(Resource, Debug, Default)]
+struct Rebinding(Option<InputAction>);
+
+fn settings_window_system(
+    mut contexts: EguiContexts,
+    mut settings_window: ResMut<SettingsWindow>,
+    mut settings: ResMut<Settings>,
+    mut keybindings: ResMut<Keybindings>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    if !settings_window.open {
+        return;
+    }
+    egui::Window::new("Settings")
+        .open(&mut settings_window.open)
+        .collapsible(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.heading("Audio");
+            ui.add(egui::Slider::new(&mut settings.master_volume, 0.0..=1.0).text("Volume"));
+
+            ui.heading("Interface");
+            ui.add(
+                egui::Slider::new(&mut settings.ui_scale, 0.5..=2.0)
+                    .step_by(0.1)
+                    .text("UI Scale"),
+            );
+            ui.horizontal(|ui| {
+                ui.label("Patience Bars");
+                for orientation in BarOrientation::ALL {
+                    ui.radio_value(
+                        &mut settings.patience_bar_orientation,
+                        orientation,
+                        orientation.name(),
+                    );
+                }
+            });
+
+            ui.heading("Speed Dial");
+            dial_response_ui(ui, &mut settings.dial);
+            ui.checkbox(&mut settings.assisted_driving, "Assisted Driving")
+                .on_hover_text("Click a floor to have the lift drive itself there");
+            ui.checkbox(&mut settings.show_ghost, "Show Ghost")
+                .on_hover_text("Race your best saved run of the same seed, from the next day");
+
+            ui.heading("Controls");
+            egui::Grid::new("keybindings")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    for action in REBINDABLE_ACTIONS.iter() {
+                        ui.label(action.name());
+                        let button_text = if rebinding.0 == Some(*action) {
+                            "Press a key...".to_string()
+                        } else {
+                            keybindings
+                                .key(action)
+                                .map_or("Unbound".to_string(), |key| input_action::key_name(&key))
+                        };
+                        if ui.button(button_text).clicked() {
+                            rebinding.0 = Some(*action);
+                        }
+                        ui.end_row();
+                    }
+                });
+            if ui.button("Reset Controls").clicked() {
+                rebinding.0 = None;
+                *keybindings = Keybindings::default();
+                keybindings.save();
+            }
+        });
+}
+
+/// Number of points sampled for the response preview graph
+const PREVIEW_SAMPLES: usize = 50;
+
+fn dial_response_ui(ui: &mut egui::Ui, dial: &mut DialResponse) {
+    let curves = [
+        ResponseCurve::Linear,
+        ResponseCurve::Quadratic,
+        ResponseCurve::Cubic,
+        dial.curve.to_custom(),
+    ];
+    egui::ComboBox::from_label("Response Curve")
+        .selected_text(dial.curve.name())
+        .show_ui(ui, |ui| {
+            for curve in curves {
+                let name = curve.name();
+                ui.selectable_value(&mut dial.curve, curve, name);
+            }
+        });
+    if let ResponseCurve::Custom(points) = &mut dial.curve {
+        ui.horizontal(|ui| {
+            for point in points.iter_mut() {
+                ui.add(egui::Slider::new(point, 0.0..=1.0).vertical());
+            }
+        });
+    }
+    ui.add(egui::Slider::new(&mut dial.deadzone, 0.0..=0.5).text("Deadzone"));
+    ui.add(egui::Slider::new(&mut dial.max_speed, 10.0..=200.0).text("Max Speed"));
+    ui.add(
+        egui::Slider::new(&mut dial.mouse_sensitivity, 0.001..=0.02)
+            .logarithmic(true)
+            .text("Mouse Sensitivity"),
+    );
+    ui.add(egui::Slider::new(&mut dial.responsiveness, 0.02..=1.0).text("Responsiveness"));
+
+    let preview: Vec<[f64; 2]> = (0..=PREVIEW_SAMPLES)
+        .map(|i| {
+            let deflection = (i as f32 / PREVIEW_SAMPLES as f32) * 2.0 - 1.0;
+            [deflection as f64, dial.speed(deflection) as f64]
+        })
+        .collect();
+    Plot::new("Dial Response")
+        .view_aspect(2.0)
+        .allow_drag(false)
+        .allow_zoom(false)
+        .allow_scroll(false)
+        .show(ui, |ui| {
+            ui.line(Line::new(PlotPoints::new(preview)).name("Speed"));
+        });
+}
+
+fn apply_volume_system(settings: Res<Settings>, audio: Res<Audio>) {
+    if settings.is_changed() {
+        audio.set_volume(settings.master_volume);
+    }
+}
+
+fn capture_rebind_system(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut keybindings: ResMut<Keybindings>,
+) {
+    let Some(action) = rebinding.0 else {
+        return;
+    };
+    if let Some(key) = keys
+        .get_just_pressed()
+        .find(|key| input_action::is_bindable(key))
+    {
+        info!(
+            "Binding {} to {}",
+            action.name(),
+            input_action::key_name(key)
+        );
+        keybindings.rebind(action, *key);
+        keybindings.save();
+        rebinding.0 = None;
+    }
+}
+
+fn apply_keybindings_system(
+    keybindings: Res<Keybindings>,
+    mut input_maps: Query<&mut InputMap<InputAction>>,
+) {
+    if keybindings.is_changed() && !keybindings.is_added() {
+        for mut input_map in input_maps.iter_mut() {
+            *input_map = keybindings.input_map();
+        }
+    }
+}
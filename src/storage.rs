@@ -0,0 +1,95 @@
+//! Small files the game keeps between runs, like keybindings, progress and high scores. On
+//! desktop they're files alongside the game, and in the browser, where there's no file system,
+//! they're kept in localStorage under the same paths
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub use backend::{list, read_to_string, write};
+
+#[cfg(not(target_arch = "wasm32"))]
+mod backend {
+    use super::*;
+    use std::fs;
+
+    pub fn read_to_string(path: impl AsRef<Path>) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+
+    /// Creates [path]'s directory first if need be
+    pub fn write(path: impl AsRef<Path>, contents: &str) -> io::Result<()> {
+        if let Some(dir) = path.as_ref().parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, contents)
+    }
+
+    /// Files in [dir] with the given [extension], sorted by name
+    pub fn list(dir: impl AsRef<Path>, extension: &str) -> Vec<PathBuf> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return vec![];
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+            .collect();
+        paths.sort();
+        paths
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod backend {
+    use super::*;
+    use web_sys::Storage;
+
+    /// Keeps the game's entries apart from anything else served from the same origin
+    const KEY_PREFIX: &str = "going_up/";
+
+    fn js_error(error: impl std::fmt::Debug) -> io::Error {
+        io::Error::new(io::ErrorKind::Other, format!("{:?}", error))
+    }
+
+    fn local_storage() -> io::Result<Storage> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "no localStorage"))
+    }
+
+    fn key_of_path(path: &Path) -> String {
+        format!(
+            "{}{}",
+            KEY_PREFIX,
+            path.to_string_lossy().replace('\\', "/")
+        )
+    }
+
+    pub fn read_to_string(path: impl AsRef<Path>) -> io::Result<String> {
+        local_storage()?
+            .get_item(&key_of_path(path.as_ref()))
+            .map_err(js_error)?
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+    }
+
+    pub fn write(path: impl AsRef<Path>, contents: &str) -> io::Result<()> {
+        local_storage()?
+            .set_item(&key_of_path(path.as_ref()), contents)
+            .map_err(js_error)
+    }
+
+    /// Entries under [dir] with the given [extension], sorted by name
+    pub fn list(dir: impl AsRef<Path>, extension: &str) -> Vec<PathBuf> {
+        let Ok(storage) = local_storage() else {
+            return vec![];
+        };
+        let dir_prefix = format!("{}/", key_of_path(dir.as_ref()));
+        let mut paths: Vec<PathBuf> = (0..storage.length().unwrap_or(0))
+            .filter_map(|i| storage.key(i).ok().flatten())
+            .filter(|key| key.starts_with(&dir_prefix))
+            .filter_map(|key| key.strip_prefix(KEY_PREFIX).map(PathBuf::from))
+            .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+            .collect();
+        paths.sort();
+        paths
+    }
+}
@@ -1,20 +1,203 @@
+//! Reusable bits of ui. [fill_bar] is for egui, the rest build Bevy UI nodes for the HUD.
+
+use bevy::prelude::*;
 use bevy_egui::egui;
 use bevy_egui::egui::Sense;
 
+pub const HUD_PANEL_COLOUR: Color = Color::rgba(0.1, 0.1, 0.1, 0.75);
+pub const HUD_TEXT_COLOUR: Color = Color::WHITE;
+const BAR_BACKGROUND_COLOUR: Color = Color::BLACK;
+
+pub struct UiWidgetsPlugin;
+
+impl Plugin for UiWidgetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, UiBar::apply_system)
+            .register_type::<UiBar>()
+            .register_type::<BarOrientation>();
+    }
+}
+
+/// Which way a bar fills, horizontal bars fill left to right and vertical ones bottom to top
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum BarOrientation {
+    #[default]
+    Horizontal,
+    Vertical,
+}
+
+impl BarOrientation {
+    pub const ALL: [Self; 2] = [Self::Horizontal, Self::Vertical];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Horizontal => "Horizontal",
+            Self::Vertical => "Vertical",
+        }
+    }
+
+    /// [size] given as (length, thickness), turned to (width, height)
+    fn oriented(&self, size: Vec2) -> Vec2 {
+        match self {
+            Self::Horizontal => size,
+            Self::Vertical => Vec2::new(size.y, size.x),
+        }
+    }
+}
+
+/// The part of [rect] filled by [fraction]
+#[cfg(feature = "debug-tools")]
+fn fill_rect(rect: egui::Rect, fraction: f32, orientation: BarOrientation) -> egui::Rect {
+    let fraction = fraction.clamp(0.0, 1.0);
+    match orientation {
+        BarOrientation::Horizontal => {
+            egui::Rect::from_min_size(rect.min, egui::vec2(rect.width() * fraction, rect.height()))
+        }
+        BarOrientation::Vertical => egui::Rect::from_min_max(
+            egui::pos2(rect.min.x, rect.max.y - rect.height() * fraction),
+            rect.max,
+        ),
+    }
+}
+
+#[cfg(feature = "debug-tools")]
 pub fn fill_bar(
     ui: &mut egui::Ui,
     bg_color: egui::Color32,
     fill_color: egui::Color32,
     fraction: f32,
+    orientation: BarOrientation,
+    rounding: f32,
 ) {
-    let desired_size = ui.spacing().interact_size * egui::vec2(1.0, 0.2);
-    let (rect, _) = ui.allocate_exact_size(desired_size, Sense::hover());
-    let fill_height = rect.height() * fraction;
-    let mut fill_rect = rect.clone();
-    fill_rect.set_height(fill_height);
+    let length = ui.spacing().interact_size.x;
+    let desired_size = orientation.oriented(Vec2::new(length, length * 0.15));
+    let (rect, _) =
+        ui.allocate_exact_size(egui::vec2(desired_size.x, desired_size.y), Sense::hover());
 
     if ui.is_rect_visible(rect) {
-        ui.painter().rect_filled(rect, 0.0, bg_color);
-        ui.painter().rect_filled(fill_rect, 0.0, fill_color);
+        ui.painter().rect_filled(rect, rounding, bg_color);
+        if fraction > 0.0 {
+            ui.painter()
+                .rect_filled(fill_rect(rect, fraction, orientation), rounding, fill_color);
+        }
+    }
+}
+
+/// A translucent box for grouping HUD elements, laid out as [style] says
+pub fn hud_panel(style: Style) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            padding: UiRect::all(Val::Px(6.0)),
+            ..style
+        },
+        background_color: HUD_PANEL_COLOUR.into(),
+        ..default()
+    }
+}
+
+pub fn hud_text(value: impl Into<String>, font_size: f32) -> TextBundle {
+    TextBundle::from_section(
+        value,
+        TextStyle {
+            font_size,
+            color: HUD_TEXT_COLOUR,
+            ..default()
+        },
+    )
+}
+
+/// A Bevy UI bar filled to [fraction]. Spawn with [spawn_bar]
+#[derive(Component, Debug, Clone, Reflect)]
+pub struct UiBar {
+    pub fraction: f32,
+    pub fill_colour: Color,
+    pub orientation: BarOrientation,
+    /// (length, thickness), whichever way it's oriented
+    size: Vec2,
+}
+
+#[derive(Component, Debug)]
+struct UiBarFill;
+
+/// Spawn a [UiBar] of [size] (length, thickness) under [parent], with [extra] added to the bar
+/// for finding it again. Its style is set up by [UiBar::apply_system]
+pub fn spawn_bar(
+    parent: &mut ChildBuilder,
+    size: Vec2,
+    fill_colour: Color,
+    orientation: BarOrientation,
+    extra: impl Bundle,
+) -> Entity {
+    parent
+        .spawn(NodeBundle {
+            background_color: BAR_BACKGROUND_COLOUR.into(),
+            ..default()
+        })
+        .insert(UiBar {
+            fraction: 1.0,
+            fill_colour,
+            orientation,
+            size,
+        })
+        .insert(extra)
+        .with_children(|bar| {
+            bar.spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(0.0),
+                    bottom: Val::Px(0.0),
+                    ..default()
+                },
+                background_color: fill_colour.into(),
+                ..default()
+            })
+            .insert(UiBarFill);
+        })
+        .id()
+}
+
+impl UiBar {
+    fn apply_system(
+        mut bar_query: Query<(&Self, &mut Style, &Children), Changed<Self>>,
+        mut fill_query: Query<(&mut Style, &mut BackgroundColor), (With<UiBarFill>, Without<Self>)>,
+    ) {
+        for (bar, mut bar_style, children) in bar_query.iter_mut() {
+            let size = bar.orientation.oriented(bar.size);
+            bar_style.width = Val::Px(size.x);
+            bar_style.height = Val::Px(size.y);
+            let filled = Val::Percent(bar.fraction.clamp(0.0, 1.0) * 100.0);
+            let (width, height) = match bar.orientation {
+                BarOrientation::Horizontal => (filled, Val::Percent(100.0)),
+                BarOrientation::Vertical => (Val::Percent(100.0), filled),
+            };
+            for child in children.iter() {
+                if let Ok((mut style, mut background)) = fill_query.get_mut(*child) {
+                    style.width = width;
+                    style.height = height;
+                    background.0 = bar.fill_colour;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "debug-tools")]
+    fn test_fill_rect() {
+        let rect = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(40.0, 10.0));
+        assert_eq!(
+            fill_rect(rect, 0.25, BarOrientation::Horizontal),
+            egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(10.0, 10.0))
+        );
+        // Vertical bars fill up from the bottom, remembering egui's y is down
+        assert_eq!(
+            fill_rect(rect, 0.5, BarOrientation::Vertical),
+            egui::Rect::from_min_max(egui::pos2(0.0, 5.0), egui::pos2(40.0, 10.0))
+        );
+        assert_eq!(fill_rect(rect, 1.5, BarOrientation::Horizontal), rect);
     }
 }